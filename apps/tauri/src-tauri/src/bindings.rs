@@ -0,0 +1,284 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::detect::git_output;
+use crate::exports::{posix_quote, render_profile_env, EnvFormat};
+use crate::lock::{require_app_unlocked, require_switch_allowed};
+use crate::paths::get_bindings_path;
+use crate::perms::write_private;
+use crate::profiles::resolve_profile;
+use crate::redact::redacted;
+use crate::shell::{find_block, separated, strip_block, BLOCK_END, BLOCK_START};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BindingFile {
+    /// A direnv `.envrc` that loads the profile at runtime, so it holds no credentials.
+    Envrc,
+    /// A `.env` file with the values written out, for tools that cannot run commands.
+    Dotenv,
+}
+
+impl BindingFile {
+    fn file_name(self) -> &'static str {
+        match self {
+            BindingFile::Envrc => ".envrc",
+            BindingFile::Dotenv => ".env",
+        }
+    }
+
+    /// A `.env` holds credentials, so it is kept owner-only.
+    fn write(self, path: &Path, content: &str) -> Result<(), String> {
+        let written = match self {
+            BindingFile::Envrc => fs::write(path, content),
+            BindingFile::Dotenv => write_private(path, content),
+        };
+        written.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirBinding {
+    pub dir: String,
+    pub profile: String,
+    pub file: BindingFile,
+}
+
+#[derive(Serialize)]
+pub struct BindingStatus {
+    #[serde(flatten)]
+    pub binding: DirBinding,
+    pub path: String,
+    /// Whether the managed block is still present in the file.
+    pub present: bool,
+    pub warnings: Vec<String>,
+}
+
+fn read_bindings() -> Result<Vec<DirBinding>, String> {
+    let path = get_bindings_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read bindings: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse bindings: {}", redacted(e)))
+}
+
+fn write_bindings(bindings: &[DirBinding]) -> Result<(), String> {
+    let path = get_bindings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create bindings directory: {}", e))?;
+    }
+
+    let pretty_json = serde_json::to_string_pretty(bindings)
+        .map_err(|e| format!("Failed to serialize bindings: {}", e))?;
+
+    fs::write(&path, pretty_json)
+        .map_err(|e| format!("Failed to write bindings: {}", e))
+}
+
+/// Bindings are keyed by absolute path so `.` and `~/work/x` match the same entry.
+fn canonical_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = fs::canonicalize(dir).map_err(|e| format!("Failed to resolve {}: {}", dir, e))?;
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    Ok(path)
+}
+
+/// The running executable, which answers `env` on the command line. The
+/// installed binary is not named `my-switch` and need not be on PATH.
+fn cli_path() -> Result<String, String> {
+    env::current_exe()
+        .map(|path| path.display().to_string())
+        .map_err(|e| format!("Failed to locate the my-switch executable: {}", e))
+}
+
+fn render_block(binding: &DirBinding) -> Result<String, String> {
+    let body = match binding.file {
+        BindingFile::Envrc => format!(
+            "# Credentials come from the my-switch profile store, not this file.\neval \"$({} env {} --shell bash --unset)\"",
+            posix_quote(&cli_path()?),
+            posix_quote(&binding.profile)
+        ),
+        BindingFile::Dotenv => format!(
+            "# Managed by my-switch. Bind the directory again after editing the profile.\n{}",
+            render_profile_env(&binding.profile, EnvFormat::Dotenv, false)?
+        ),
+    };
+
+    Ok(format!("{}\n{}\n{}\n", BLOCK_START, body, BLOCK_END))
+}
+
+/// Keeps a `.env` holding credentials out of git: excludes it locally when
+/// the directory is a work tree, and warns if it is already tracked.
+fn protect_dotenv(dir: &Path, file: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let Ok(exclude) = git_output(dir, &["rev-parse", "--git-path", "info/exclude"]) else {
+        return warnings;
+    };
+    let exclude = dir.join(exclude);
+    let name = format!("/{}", BindingFile::Dotenv.file_name());
+
+    let content = fs::read_to_string(&exclude).unwrap_or_default();
+    if !content.lines().any(|line| line.trim() == name) {
+        let mut updated = content;
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str(&name);
+        updated.push('\n');
+
+        let written = exclude.parent().is_none_or(|parent| fs::create_dir_all(parent).is_ok())
+            && fs::write(&exclude, updated).is_ok();
+        if !written {
+            warnings.push(format!("Could not add {} to {}", name, exclude.display()));
+        }
+    }
+
+    if git_output(dir, &["ls-files", "--error-unmatch", "--", BindingFile::Dotenv.file_name()]).is_ok() {
+        warnings.push(format!(
+            "{} is tracked by git and now contains credentials; untrack it with `git rm --cached`",
+            file.display()
+        ));
+    }
+
+    warnings
+}
+
+/// Writes or refreshes the managed block for the binding and returns any warnings.
+fn apply_binding(binding: &DirBinding) -> Result<Vec<String>, String> {
+    let dir = PathBuf::from(&binding.dir);
+    let path = dir.join(binding.file.file_name());
+    let block = render_block(binding)?;
+
+    let content = if path.exists() {
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+    } else {
+        String::new()
+    };
+
+    let updated = match find_block(&content) {
+        Some((start, end)) => format!("{}{}{}", &content[..start], block.trim_end_matches('\n'), &content[end..]),
+        None => format!("{}{}", separated(&content), block),
+    };
+
+    if updated != content {
+        binding.file.write(&path, &updated)?;
+    }
+
+    let mut warnings = Vec::new();
+    if binding.file == BindingFile::Dotenv {
+        warnings.extend(protect_dotenv(&dir, &path));
+    } else {
+        warnings.push(format!("Run `direnv allow {}` to load the profile", dir.display()));
+    }
+    Ok(warnings)
+}
+
+fn binding_status(binding: DirBinding, warnings: Vec<String>) -> BindingStatus {
+    let path = Path::new(&binding.dir).join(binding.file.file_name());
+    let present = fs::read_to_string(&path)
+        .map(|content| find_block(&content).is_some())
+        .unwrap_or(false);
+
+    BindingStatus {
+        path: path.display().to_string(),
+        present,
+        warnings,
+        binding,
+    }
+}
+
+/// Binds `dir` to a profile, replacing any earlier binding of the same directory.
+#[tauri::command]
+pub fn bind_directory(dir: String, profile: String, file: BindingFile) -> Result<BindingStatus, String> {
+    require_switch_allowed()?;
+    if file == BindingFile::Dotenv {
+        // A `.env` gets the resolved token, which is an export the lock forbids
+        require_app_unlocked()?;
+    }
+    let dir = canonical_dir(&dir)?;
+    // Fail now rather than when the shell next enters the directory
+    resolve_profile(&profile)?;
+
+    let binding = DirBinding {
+        dir: dir.display().to_string(),
+        profile,
+        file,
+    };
+
+    let mut bindings = read_bindings()?;
+    if let Some(old) = bindings.iter().find(|b| b.dir == binding.dir && b.file != file) {
+        // Switching files leaves no stale block behind in the old one
+        remove_block(old)?;
+    }
+    bindings.retain(|b| b.dir != binding.dir);
+
+    let warnings = apply_binding(&binding)?;
+    bindings.push(binding.clone());
+    write_bindings(&bindings)?;
+
+    Ok(binding_status(binding, warnings))
+}
+
+/// The `.env` files bindings wrote credentials into.
+pub(crate) fn dotenv_files() -> Vec<PathBuf> {
+    read_bindings()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|b| b.file == BindingFile::Dotenv)
+        .map(|b| Path::new(&b.dir).join(BindingFile::Dotenv.file_name()))
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_bindings() -> Result<Vec<BindingStatus>, String> {
+    Ok(read_bindings()?
+        .into_iter()
+        .map(|binding| binding_status(binding, Vec::new()))
+        .collect())
+}
+
+/// Strips the managed block, deleting the file if nothing else is left in it.
+fn remove_block(binding: &DirBinding) -> Result<(), String> {
+    let path = Path::new(&binding.dir).join(binding.file.file_name());
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let Some(stripped) = strip_block(&content) else {
+        return Ok(());
+    };
+
+    if stripped.trim().is_empty() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+    } else {
+        binding.file.write(&path, &stripped)
+    }
+}
+
+/// Removes the binding and its managed block. The directory may already be gone.
+#[tauri::command]
+pub fn remove_binding(dir: String) -> Result<Vec<BindingStatus>, String> {
+    require_switch_allowed()?;
+    let key = canonical_dir(&dir)
+        .map(|path| path.display().to_string())
+        .unwrap_or(dir);
+
+    let mut bindings = read_bindings()?;
+    let Some(index) = bindings.iter().position(|b| b.dir == key) else {
+        return Err(format!("No binding for {}", key));
+    };
+
+    let binding = bindings.remove(index);
+    remove_block(&binding)?;
+    write_bindings(&bindings)?;
+
+    list_bindings()
+}
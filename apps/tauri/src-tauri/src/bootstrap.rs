@@ -0,0 +1,135 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::paths::{
+    get_claude_settings_path, get_codex_auth_path, get_codex_config_path, get_droid_settings_path,
+    get_opencode_config_path, get_secrets_path,
+};
+#[cfg(unix)]
+use crate::perms::PRIVATE_MODE;
+use crate::tools::Tool;
+
+#[derive(Serialize)]
+pub struct InitReport {
+    pub tool: Tool,
+    pub created: Vec<String>,
+    /// Files that were already present and left untouched.
+    pub existing: Vec<String>,
+}
+
+const SECRETS_TEMPLATE: &str = r#"# Claude Code
+export ANTHROPIC_BASE_URL=""
+export ANTHROPIC_AUTH_TOKEN=""
+"#;
+
+pub(crate) const CODEX_CONFIG_TEMPLATE: &str = r#"model_provider = "custom"
+
+[model_providers.custom]
+name = "custom"
+base_url = ""
+wire_api = "responses"
+requires_openai_auth = true
+"#;
+
+fn pretty(value: serde_json::Value) -> String {
+    // Serializing a json! literal cannot fail
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+fn templates(tool: Tool) -> Result<Vec<(PathBuf, String)>, String> {
+    Ok(match tool {
+        Tool::Claude => vec![
+            (get_secrets_path()?, SECRETS_TEMPLATE.to_string()),
+            (
+                get_claude_settings_path()?,
+                pretty(json!({
+                    "$schema": "https://json.schemastore.org/claude-code-settings.json",
+                    "env": {}
+                })),
+            ),
+        ],
+        Tool::Codex => vec![
+            (get_codex_config_path()?, CODEX_CONFIG_TEMPLATE.to_string()),
+            (get_codex_auth_path()?, pretty(json!({ "OPENAI_API_KEY": "" }))),
+        ],
+        Tool::Droid => vec![(
+            get_droid_settings_path()?,
+            pretty(json!({
+                "customModels": [{
+                    "model": "",
+                    "displayName": "Custom",
+                    "baseUrl": "",
+                    "apiKey": "",
+                    "provider": "generic-chat-completion-api",
+                    "maxOutputTokens": 16384
+                }]
+            })),
+        )],
+        Tool::Opencode => vec![(
+            get_opencode_config_path()?,
+            pretty(json!({
+                "$schema": "https://opencode.ai/config.json",
+                "provider": {
+                    "openai": {
+                        "options": {
+                            "baseURL": "",
+                            "apiKey": ""
+                        }
+                    }
+                }
+            })),
+        )],
+    })
+}
+
+/// Creates `path` with `content`. Returns false if the file already exists.
+fn create_file(path: &Path, content: &str) -> Result<bool, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    // create_new makes the existence check and the create a single step.
+    // Templates get tokens later, so they start out private like write_private's files
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(PRIVATE_MODE);
+    }
+
+    let mut file = match options.open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+    };
+
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn init_tool_config(tool: Tool) -> Result<InitReport, String> {
+    let mut report = InitReport {
+        tool,
+        created: Vec::new(),
+        existing: Vec::new(),
+    };
+
+    for (path, content) in templates(tool)? {
+        let display = path.display().to_string();
+        if create_file(&path, &content)? {
+            report.created.push(display);
+        } else {
+            report.existing.push(display);
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,479 @@
+use std::fs;
+use serde::{Deserialize, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::lock::require_app_unlocked;
+use crate::paths::expand_home;
+use crate::perms::write_private;
+use crate::profiles::{read_profiles, resolve_profile, save_profiles, Protocol, ProviderProfile};
+use crate::redact::{redacted, value_fingerprint};
+use crate::secret::Secret;
+use crate::secret_refs::is_reference;
+use crate::validate::{check_token, Severity};
+use crate::vault::{seal, unseal, Sealed};
+
+const FORMAT: &str = "my-switch-profiles";
+const VERSION: u32 = 1;
+/// Binds the sealed profiles to the bundle format, like the vault's own.
+const ASSOCIATED_DATA: &[u8] = b"my-switch-bundle-v1";
+
+/// A profile as it travels in a bundle: no id, since ids are local to each
+/// profile store, and the token in plaintext or left out.
+#[derive(Serialize, Deserialize)]
+struct BundleProfile {
+    name: String,
+    base_url: String,
+    #[serde(default, serialize_with = "exposed")]
+    api_key: Secret,
+    protocol: Protocol,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+fn exposed<S: Serializer>(secret: &Secret, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(secret.expose())
+}
+
+/// The file `export_profiles` writes. Encrypted bundles carry the profiles
+/// sealed under the bundle's passphrase instead of in the clear.
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    format: String,
+    version: u32,
+    tokens_included: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profiles: Option<Vec<BundleProfile>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed: Option<Sealed>,
+}
+
+#[derive(Serialize)]
+pub struct ExportReport {
+    pub path: String,
+    pub profiles: usize,
+    pub encrypted: bool,
+    pub tokens_included: bool,
+}
+
+#[derive(Serialize)]
+pub struct ImportCandidate {
+    /// Position in the bundle, which import decisions refer to.
+    pub index: usize,
+    pub name: String,
+    pub base_url: String,
+    pub protocol: Protocol,
+    pub model: Option<String>,
+    pub key_fingerprint: Option<String>,
+    /// The saved profile with the same name, if there is one.
+    pub conflict: Option<String>,
+    /// Problems with the token that would stop the import.
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportPreview {
+    pub encrypted: bool,
+    pub tokens_included: bool,
+    pub profiles: Vec<ImportCandidate>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    Import,
+    Skip,
+    Rename,
+    Overwrite,
+}
+
+#[derive(Deserialize)]
+pub struct ImportDecision {
+    pub index: usize,
+    pub action: ImportAction,
+    /// The name to import under, for `rename`.
+    #[serde(default)]
+    pub rename_to: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn bundle_profile(profile: ProviderProfile, include_tokens: bool) -> BundleProfile {
+    BundleProfile {
+        name: profile.name,
+        base_url: profile.base_url,
+        api_key: if include_tokens { profile.api_key } else { Secret::default() },
+        protocol: profile.protocol,
+        model: profile.model,
+    }
+}
+
+/// Wraps the profiles in a bundle, sealed under `passphrase` if there is one.
+fn build_bundle(
+    profiles: Vec<BundleProfile>,
+    passphrase: Option<&str>,
+    include_tokens: bool,
+) -> Result<Bundle, String> {
+    let Some(passphrase) = passphrase else {
+        return Ok(Bundle {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            tokens_included: include_tokens,
+            profiles: Some(profiles),
+            sealed: None,
+        });
+    };
+
+    let mut plaintext = serde_json::to_vec(&profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    let sealed = seal(&plaintext, passphrase, ASSOCIATED_DATA);
+    plaintext.zeroize();
+    Ok(Bundle {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        tokens_included: include_tokens,
+        profiles: None,
+        sealed: Some(sealed?),
+    })
+}
+
+/// Writes the profiles named by `selectors` (saved profile ids or names, or
+/// `cs:N` and `anthropic:N` for the shell groups) to a bundle at `path`.
+/// Tokens are resolved, so a bundle never refers to this machine's vault,
+/// environment or password manager. An existing file is only replaced with
+/// `overwrite`.
+#[tauri::command]
+pub fn export_profiles(
+    selectors: Vec<String>,
+    path: String,
+    passphrase: Option<String>,
+    include_tokens: bool,
+    overwrite: bool,
+) -> Result<ExportReport, String> {
+    require_app_unlocked()?;
+    if selectors.is_empty() {
+        return Err("Select at least one profile to export".to_string());
+    }
+    let target = expand_home(&path)?;
+    if target.exists() && !overwrite {
+        return Err(format!("{} already exists; choose overwrite to replace it", target.display()));
+    }
+
+    let mut profiles = Vec::new();
+    for selector in &selectors {
+        // Without tokens, saved profiles are taken as they are, so nothing is
+        // looked up in a password manager just to be left out
+        let saved = read_profiles()?
+            .into_iter()
+            .find(|p| p.id == *selector || p.name.eq_ignore_ascii_case(selector));
+        let profile = match saved {
+            Some(profile) if !include_tokens => profile,
+            _ => resolve_profile(selector)?,
+        };
+        profiles.push(bundle_profile(profile, include_tokens));
+    }
+    let count = profiles.len();
+
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let bundle = build_bundle(profiles, passphrase.as_deref(), include_tokens)?;
+
+    let mut pretty_json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+    let written = write_private(&target, &pretty_json);
+    pretty_json.zeroize();
+    written.map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+
+    Ok(ExportReport {
+        path: target.display().to_string(),
+        profiles: count,
+        encrypted: passphrase.is_some(),
+        tokens_included: include_tokens,
+    })
+}
+
+/// Reads a bundle, opening it with `passphrase` if it is encrypted.
+fn read_bundle(path: &str, passphrase: Option<&str>) -> Result<(Bundle, Vec<BundleProfile>), String> {
+    let path = expand_home(path)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut bundle: Bundle = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse bundle: {}", redacted(e)))?;
+
+    if bundle.format != FORMAT {
+        return Err(format!("{} is not a my-switch profile bundle", path.display()));
+    }
+    if bundle.version > VERSION {
+        return Err(format!(
+            "The bundle was written by a newer my-switch (version {}); update to import it",
+            bundle.version
+        ));
+    }
+
+    let profiles = match (bundle.profiles.take(), &bundle.sealed) {
+        (Some(profiles), _) => profiles,
+        (None, Some(sealed)) => {
+            let passphrase = passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| "The bundle is encrypted; enter its passphrase".to_string())?;
+            let mut plaintext = unseal(sealed, passphrase, ASSOCIATED_DATA)?;
+            let profiles = serde_json::from_slice(&plaintext)
+                .map_err(|e| format!("Failed to parse bundle: {}", redacted(e)));
+            plaintext.zeroize();
+            profiles?
+        }
+        (None, None) => Vec::new(),
+    };
+    Ok((bundle, profiles))
+}
+
+/// What would stop a bundled token from being saved. Bundles carry tokens,
+/// never references, so a bundle cannot make this machine run a command.
+fn token_errors(profile: &BundleProfile) -> Vec<String> {
+    let token = profile.api_key.expose();
+    if token.is_empty() {
+        return Vec::new();
+    }
+    if is_reference(token) {
+        return vec!["The bundle holds a reference instead of a token".to_string()];
+    }
+    check_token(token, profile.protocol, &profile.base_url)
+        .into_iter()
+        .filter(|i| i.severity == Severity::Error)
+        .map(|i| i.message)
+        .collect()
+}
+
+fn find_by_name<'a>(profiles: &'a [ProviderProfile], name: &str) -> Option<&'a ProviderProfile> {
+    profiles.iter().find(|p| p.name.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Lists the profiles in a bundle and which of them clash with saved ones,
+/// so each clash can be skipped, renamed or overwritten.
+#[tauri::command]
+pub fn preview_import(path: String, passphrase: Option<String>) -> Result<ImportPreview, String> {
+    require_app_unlocked()?;
+    let (bundle, profiles) = read_bundle(&path, passphrase.as_deref())?;
+    let existing = read_profiles()?;
+
+    let profiles = profiles
+        .iter()
+        .enumerate()
+        .map(|(index, profile)| ImportCandidate {
+            index,
+            name: profile.name.clone(),
+            base_url: profile.base_url.clone(),
+            protocol: profile.protocol,
+            model: profile.model.clone(),
+            key_fingerprint: value_fingerprint(profile.api_key.expose()),
+            conflict: find_by_name(&existing, &profile.name).map(|p| p.id.clone()),
+            errors: token_errors(profile),
+        })
+        .collect();
+
+    Ok(ImportPreview {
+        encrypted: bundle.sealed.is_some(),
+        tokens_included: bundle.tokens_included,
+        profiles,
+    })
+}
+
+/// Imports a bundle into the profile store. Profiles without a decision are
+/// imported when their name is free; every clash needs one. The import is all
+/// or nothing: every profile is checked and merged before the profile store
+/// is written, once, and a decision for a profile the bundle does not have
+/// fails the import. Overwriting with a bundle that has no tokens keeps the
+/// saved token.
+#[tauri::command]
+pub fn import_profiles(
+    path: String,
+    passphrase: Option<String>,
+    decisions: Vec<ImportDecision>,
+) -> Result<ImportReport, String> {
+    require_app_unlocked()?;
+    let (_, profiles) = read_bundle(&path, passphrase.as_deref())?;
+    for (i, decision) in decisions.iter().enumerate() {
+        if decision.index >= profiles.len() {
+            return Err(format!("The bundle has no profile at index {}", decision.index));
+        }
+        if decisions[..i].iter().any(|d| d.index == decision.index) {
+            return Err(format!("More than one decision for the profile at index {}", decision.index));
+        }
+    }
+    let existing = read_profiles()?;
+
+    let mut planned: Vec<(ProviderProfile, bool)> = Vec::new();
+    let mut report = ImportReport::default();
+    for (index, profile) in profiles.into_iter().enumerate() {
+        let decision = decisions.iter().find(|d| d.index == index);
+        let action = decision.map(|d| d.action).unwrap_or(ImportAction::Import);
+        let conflict = find_by_name(&existing, &profile.name);
+
+        let (id, name, api_key) = match (action, conflict) {
+            (ImportAction::Skip, _) => {
+                report.skipped.push(profile.name.clone());
+                continue;
+            }
+            (ImportAction::Import, None) => (String::new(), profile.name.clone(), profile.api_key.clone()),
+            (ImportAction::Import, Some(_)) => {
+                return Err(format!("{} already exists; choose skip, rename or overwrite", profile.name));
+            }
+            (ImportAction::Rename, _) => {
+                let name = decision
+                    .and_then(|d| d.rename_to.clone())
+                    .filter(|n| !n.trim().is_empty())
+                    .ok_or_else(|| format!("A new name is required to rename {}", profile.name))?;
+                (String::new(), name, profile.api_key.clone())
+            }
+            (ImportAction::Overwrite, None) => (String::new(), profile.name.clone(), profile.api_key.clone()),
+            (ImportAction::Overwrite, Some(current)) => {
+                let api_key = if profile.api_key.is_empty() { current.api_key.clone() } else { profile.api_key.clone() };
+                (current.id.clone(), current.name.clone(), api_key)
+            }
+        };
+
+        if id.is_empty()
+            && (find_by_name(&existing, &name).is_some()
+                || planned.iter().any(|(p, _)| p.name.trim().eq_ignore_ascii_case(name.trim())))
+        {
+            return Err(format!("A profile named {} already exists", name));
+        }
+        if let Some(error) = token_errors(&profile).into_iter().next() {
+            return Err(format!("{}: {}", profile.name, error));
+        }
+
+        let overwrite = !id.is_empty();
+        planned.push((
+            ProviderProfile {
+                id,
+                name,
+                base_url: profile.base_url.clone(),
+                api_key,
+                protocol: profile.protocol,
+                model: profile.model.clone(),
+                key_fingerprint: None,
+            },
+            overwrite,
+        ));
+    }
+
+    let overwritten: Vec<bool> = planned.iter().map(|(_, overwrite)| *overwrite).collect();
+    let saved = save_profiles(planned.into_iter().map(|(profile, _)| profile).collect())?;
+    for (profile, overwrite) in saved.into_iter().zip(overwritten) {
+        if overwrite {
+            report.overwritten.push(profile.name);
+        } else {
+            report.imported.push(profile.name);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::merged_profiles;
+
+    fn profiles() -> Vec<BundleProfile> {
+        vec![BundleProfile {
+            name: "work".to_string(),
+            base_url: "https://gateway.example.com".to_string(),
+            api_key: Secret::new("sk-bundle-token-123456"),
+            protocol: Protocol::Anthropic,
+            model: Some("claude-sonnet".to_string()),
+        }]
+    }
+
+    /// Writes the bundle as `export_profiles` does and reads it back.
+    fn round_trip(
+        bundle: &Bundle,
+        passphrase: Option<&str>,
+    ) -> (tempfile::TempDir, Result<(Bundle, Vec<BundleProfile>), String>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+        fs::write(&path, serde_json::to_string_pretty(bundle).unwrap()).unwrap();
+        let read = read_bundle(&path.display().to_string(), passphrase);
+        (dir, read)
+    }
+
+    #[test]
+    fn plain_bundles_round_trip() {
+        let bundle = build_bundle(profiles(), None, true).unwrap();
+        let (dir, read) = round_trip(&bundle, None);
+        let (read, profiles) = read.unwrap();
+
+        let content = fs::read_to_string(dir.path().join("profiles.json")).unwrap();
+        assert!(content.contains("sk-bundle-token-123456"));
+        assert!(read.sealed.is_none());
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "work");
+        assert_eq!(profiles[0].api_key.expose(), "sk-bundle-token-123456");
+        assert_eq!(profiles[0].model.as_deref(), Some("claude-sonnet"));
+    }
+
+    #[test]
+    fn encrypted_bundles_round_trip() {
+        let bundle = build_bundle(profiles(), Some("correct horse battery"), true).unwrap();
+        let (dir, read) = round_trip(&bundle, Some("correct horse battery"));
+        let (read, profiles) = read.unwrap();
+
+        let content = fs::read_to_string(dir.path().join("profiles.json")).unwrap();
+        assert!(!content.contains("sk-bundle-token-123456"));
+        assert!(!content.contains("work"));
+        assert!(read.sealed.is_some());
+        assert_eq!(profiles[0].api_key.expose(), "sk-bundle-token-123456");
+    }
+
+    #[test]
+    fn encrypted_bundles_need_the_passphrase() {
+        let bundle = build_bundle(profiles(), Some("correct horse battery"), true).unwrap();
+        assert!(round_trip(&bundle, None).1.is_err());
+        assert!(round_trip(&bundle, Some("wrong horse battery")).1.is_err());
+    }
+
+    #[test]
+    fn other_files_are_not_bundles() {
+        let mut bundle = build_bundle(profiles(), None, true).unwrap();
+        bundle.format = "something-else".to_string();
+        assert!(round_trip(&bundle, None).1.is_err());
+    }
+
+    fn saved(id: &str, name: &str) -> ProviderProfile {
+        ProviderProfile {
+            id: id.to_string(),
+            name: name.to_string(),
+            base_url: "https://gateway.example.com".to_string(),
+            api_key: Secret::new("sk-saved-token-123456"),
+            protocol: Protocol::Anthropic,
+            model: None,
+            key_fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn imports_merge_before_anything_is_written() {
+        let existing = vec![saved("work", "work")];
+        let mut overwrite = saved("work", "work");
+        overwrite.api_key = Secret::new("sk-bundle-token-123456");
+        let new = saved("", "work");
+
+        let (merged, ids) = merged_profiles(&existing, vec![overwrite, new]).unwrap();
+        assert_eq!(ids, ["work", "work-2"]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].api_key.expose(), "sk-bundle-token-123456");
+        assert_eq!(existing[0].api_key.expose(), "sk-saved-token-123456");
+    }
+
+    #[test]
+    fn one_bad_profile_fails_the_whole_import() {
+        let existing = vec![saved("work", "work")];
+        let mut bad = saved("", "broken");
+        bad.api_key = Secret::new("sk-token with-space");
+
+        assert!(merged_profiles(&existing, vec![saved("", "fine"), bad]).is_err());
+        assert_eq!(existing.len(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::bindings::{bind_directory, list_bindings, remove_binding, BindingFile, BindingStatus};
+use crate::exec::exec_with_profile;
+use crate::exports::{render_profile_env, EnvFormat};
+use crate::leaks::scan_for_leaks;
+use crate::redact::redacted;
+
+const USAGE: &str = "Usage:
+  my-switch exec --profile <profile> -- <command> [args...]
+  my-switch env [profile] [--shell zsh|bash|fish|powershell|dotenv] [--unset]
+  my-switch bind <profile> [dir] [--dotenv]
+  my-switch unbind [dir]
+  my-switch bindings
+  my-switch scan [path...]
+
+`env` prints exports for the profile, e.g. eval \"$(my-switch env glm)\".
+Without a profile it uses the active Anthropic group. --unset also unsets
+variables left over from a previously active profile.
+
+`bind` adds a managed block to the directory's .envrc (or .env with
+--dotenv) that loads the profile. The directory defaults to the current one.
+
+`scan` searches the paths, or shell history and known projects, for the
+tokens my-switch manages and exits with 1 when it finds any.
+
+Profiles are saved profile ids or names, or `anthropic`, `cs`,
+`anthropic:N` and `cs:N` for groups in the secrets file.";
+
+fn exec_command(args: &[String]) -> Result<i32, String> {
+    let mut profile = None;
+    let mut i = 0;
+
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--" {
+            i += 1;
+            break;
+        } else if arg == "--profile" || arg == "-p" {
+            profile = Some(args.get(i + 1).ok_or("--profile needs a value")?.clone());
+            i += 2;
+        } else if let Some(value) = arg.strip_prefix("--profile=") {
+            profile = Some(value.to_string());
+            i += 1;
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option: {}", arg));
+        } else {
+            // The command starts at the first positional argument
+            break;
+        }
+    }
+
+    let profile = profile.ok_or("--profile is required")?;
+    exec_with_profile(&profile, &args[i..])
+}
+
+fn env_command(args: &[String]) -> Result<i32, String> {
+    let mut profile = None;
+    let mut format = None;
+    let mut unset = false;
+    let mut i = 0;
+
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--shell" || arg == "-s" {
+            format = Some(EnvFormat::parse(args.get(i + 1).ok_or("--shell needs a value")?)?);
+            i += 2;
+            continue;
+        }
+
+        if let Some(value) = arg.strip_prefix("--shell=") {
+            format = Some(EnvFormat::parse(value)?);
+        } else if arg == "--unset" {
+            unset = true;
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option: {}", arg));
+        } else if profile.is_none() {
+            profile = Some(arg.to_string());
+        } else {
+            return Err(format!("Unexpected argument: {}", arg));
+        }
+        i += 1;
+    }
+
+    let output = render_profile_env(
+        profile.as_deref().unwrap_or("anthropic"),
+        format.unwrap_or_else(EnvFormat::detect),
+        unset,
+    )?;
+    println!("{}", output);
+    Ok(0)
+}
+
+fn print_binding(status: &BindingStatus) {
+    let missing = if status.present { "" } else { " (block missing)" };
+    println!("{} -> {}{}", status.path, status.binding.profile, missing);
+}
+
+fn bind_command(args: &[String]) -> Result<i32, String> {
+    let mut file = BindingFile::Envrc;
+    let mut positional = Vec::new();
+
+    for arg in args {
+        if arg == "--dotenv" {
+            file = BindingFile::Dotenv;
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option: {}", arg));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    let (profile, dir) = match positional.as_slice() {
+        [profile] => (profile.clone(), ".".to_string()),
+        [profile, dir] => (profile.clone(), dir.clone()),
+        [] => return Err("A profile is required".to_string()),
+        _ => return Err("Too many arguments".to_string()),
+    };
+
+    let status = bind_directory(dir, profile, file)?;
+    print_binding(&status);
+    for warning in &status.warnings {
+        eprintln!("my-switch: {}", warning);
+    }
+    Ok(0)
+}
+
+fn unbind_command(args: &[String]) -> Result<i32, String> {
+    let dir = match args {
+        [] => ".".to_string(),
+        [dir] => dir.clone(),
+        _ => return Err("Too many arguments".to_string()),
+    };
+    remove_binding(dir)?;
+    Ok(0)
+}
+
+fn bindings_command() -> Result<i32, String> {
+    for status in list_bindings()? {
+        print_binding(&status);
+    }
+    Ok(0)
+}
+
+fn scan_command(args: &[String]) -> Result<i32, String> {
+    if let Some(option) = args.iter().find(|a| a.starts_with('-')) {
+        return Err(format!("Unknown option: {}", option));
+    }
+
+    let paths = if args.is_empty() { None } else { Some(args.to_vec()) };
+    let report = scan_for_leaks(paths)?;
+    for hit in &report.hits {
+        println!("{}:{}: {} ({})", hit.path, hit.line, hit.masked, hit.source);
+    }
+    for source in &report.skipped {
+        eprintln!("my-switch: not searched for the token of {}", source);
+    }
+    eprintln!("my-switch: {} files scanned, {} hits", report.scanned_files, report.hits.len());
+
+    Ok(if report.hits.is_empty() { 0 } else { 1 })
+}
+
+/// Runs a command-line subcommand and returns its exit code, or None when
+/// the arguments are not a subcommand and the app should start normally.
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+
+    let result = match command.as_str() {
+        "exec" => exec_command(rest),
+        "env" => env_command(rest),
+        "bind" => bind_command(rest),
+        "unbind" => unbind_command(rest),
+        "bindings" => bindings_command(),
+        "scan" => scan_command(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        _ => return None,
+    };
+
+    Some(match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("my-switch: {}", redacted(e));
+            eprintln!("{}", USAGE);
+            2
+        }
+    })
+}
@@ -0,0 +1,268 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use serde_json::Value;
+use toml::Value as TomlValue;
+
+use crate::profiles::read_profiles;
+use crate::redact::redacted;
+use crate::tools::Tool;
+use crate::secret::Secret;
+use crate::secret_refs::{cached_token, resolve_token, runs_command};
+use crate::{load_anthropic_config, load_codex_config, load_droid_config, load_opencode_config};
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+const GIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+pub struct ConfigFileStatus {
+    pub path: String,
+    pub exists: bool,
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ToolStatus {
+    pub tool: Tool,
+    pub name: String,
+    /// Whether the tool's binary was found on PATH.
+    pub installed: bool,
+    pub binary_path: Option<String>,
+    pub version: Option<String>,
+    /// Whether every managed config file exists and parses.
+    pub configured: bool,
+    pub config_files: Vec<ConfigFileStatus>,
+    pub active_base_url: Option<String>,
+    /// The saved profile whose key matches the tool's active key, if any.
+    pub active_profile: Option<String>,
+}
+
+/// Directories that GUI apps often miss because they are only added to PATH
+/// by interactive shells.
+fn extra_bin_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/usr/local/bin"), PathBuf::from("/opt/homebrew/bin")];
+    if let Some(home) = dirs::home_dir() {
+        for dir in [".local/bin", ".bun/bin", ".npm-global/bin", ".volta/bin", ".cargo/bin"] {
+            dirs.push(home.join(dir));
+        }
+    }
+    dirs
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path)
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+pub(crate) fn find_binary(name: &str) -> Option<PathBuf> {
+    let mut search: Vec<PathBuf> = env::var_os("PATH")
+        .map(|p| env::split_paths(&p).collect())
+        .unwrap_or_default();
+    search.extend(extra_bin_dirs());
+
+    let candidates: Vec<String> = if cfg!(windows) {
+        ["exe", "cmd", "bat"].iter().map(|ext| format!("{}.{}", name, ext)).collect()
+    } else {
+        vec![name.to_string()]
+    };
+
+    search
+        .iter()
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .find(|path| is_executable(path))
+}
+
+fn spawn_reader<R: Read + Send + 'static>(mut pipe: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut out = String::new();
+        let _ = pipe.read_to_string(&mut out);
+        out
+    })
+}
+
+/// Runs a command and returns its trimmed stdout, killing it if it does not
+/// finish within `timeout`.
+pub(crate) fn command_output(mut command: Command, timeout: Duration) -> Result<String, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run command: {}", e))?;
+
+    // Drain the pipes on their own threads so a chatty child cannot block
+    let stdout_reader = child.stdout.take().map(spawn_reader);
+    let stderr_reader = child.stderr.take().map(spawn_reader);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Command timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Failed to wait for command: {}", e)),
+        }
+    };
+
+    let stdout = stdout_reader.and_then(|h| h.join().ok()).unwrap_or_default();
+    let stderr = stderr_reader.and_then(|h| h.join().ok()).unwrap_or_default();
+
+    if !status.success() {
+        let detail = stderr.lines().next().unwrap_or("").trim().to_string();
+        return Err(format!("Command exited with {}: {}", status, redacted(detail)));
+    }
+
+    Ok(stdout.trim().to_string())
+}
+
+/// Runs git in `dir`. Fails when git is missing or `dir` is not a work tree.
+pub(crate) fn git_output(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    command_output(command, GIT_TIMEOUT)
+}
+
+fn binary_version(path: &Path) -> Option<String> {
+    let mut command = Command::new(path);
+    command.arg("--version");
+    command_output(command, VERSION_TIMEOUT)
+        .ok()
+        .and_then(|out| out.lines().next().map(|l| l.trim().to_string()))
+        .filter(|v| !v.is_empty())
+}
+
+fn config_file_status(path: &Path) -> ConfigFileStatus {
+    let display = path.display().to_string();
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            let exists = path.exists();
+            return ConfigFileStatus {
+                path: display,
+                exists,
+                valid: false,
+                error: exists.then(|| format!("Failed to read file: {}", e)),
+            };
+        }
+    };
+
+    let parsed = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str::<Value>(&content)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to parse JSON: {}", redacted(e))),
+        Some("toml") => content
+            .parse::<TomlValue>()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to parse TOML: {}", redacted(e))),
+        _ => Ok(()),
+    };
+
+    ConfigFileStatus {
+        path: display,
+        exists: true,
+        valid: parsed.is_ok(),
+        error: parsed.err(),
+    }
+}
+
+/// The base URL and key the tool is currently configured with.
+fn active_credentials(tool: Tool) -> Option<(String, Secret)> {
+    let (base_url, key) = match tool {
+        Tool::Claude => load_anthropic_config().ok().map(|c| (c.base_url, c.auth_token))?,
+        Tool::Codex => load_codex_config().ok().map(|c| (c.base_url, c.api_key))?,
+        Tool::Droid => load_droid_config().ok().map(|c| (c.base_url, c.api_key))?,
+        Tool::Opencode => load_opencode_config().ok().map(|c| (c.base_url, c.api_key))?,
+    };
+
+    if base_url.is_empty() && key.is_empty() {
+        None
+    } else {
+        Some((base_url, key))
+    }
+}
+
+/// The token a field stands for, if that can be found without running a
+/// command or asking a password manager; their results are used only while
+/// cached. Status refreshes must not prompt.
+fn quiet_token(value: &str) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+    if runs_command(value) {
+        return cached_token(value);
+    }
+    resolve_token(value).ok()
+}
+
+fn tool_status(tool: Tool) -> Result<ToolStatus, String> {
+    let binary = find_binary(tool.binary_name());
+    let version = binary.as_deref().and_then(binary_version);
+
+    let config_files: Vec<ConfigFileStatus> = tool
+        .config_files()?
+        .iter()
+        .map(|p| config_file_status(p))
+        .collect();
+    let configured = config_files.iter().all(|f| f.exists && f.valid);
+
+    let active = active_credentials(tool);
+    let active_profile = active.as_ref().filter(|(_, key)| !key.is_empty()).and_then(|(_, key)| {
+        let active_token = quiet_token(key.expose());
+        read_profiles()
+            .ok()?
+            .into_iter()
+            .find(|p| {
+                // The same reference matches without being resolved
+                p.api_key == *key
+                    || active_token.is_some() && quiet_token(p.api_key.expose()) == active_token
+            })
+            .map(|p| p.name)
+    });
+
+    Ok(ToolStatus {
+        tool,
+        name: tool.display_name().to_string(),
+        installed: binary.is_some(),
+        binary_path: binary.map(|p| p.display().to_string()),
+        version,
+        configured,
+        config_files,
+        active_base_url: active.map(|(url, _)| url).filter(|u| !u.is_empty()),
+        active_profile,
+    })
+}
+
+/// Runs off the main thread, since the probes wait on other processes.
+#[tauri::command(async)]
+pub fn detect_tools() -> Result<Vec<ToolStatus>, String> {
+    // Version probes spawn processes, so check the tools in parallel
+    let handles: Vec<_> = Tool::ALL
+        .into_iter()
+        .map(|tool| thread::spawn(move || tool_status(tool)))
+        .collect();
+
+    handles
+        .into_iter()
+        .map(|h| h.join().map_err(|_| "Tool detection panicked".to_string())?)
+        .collect()
+}
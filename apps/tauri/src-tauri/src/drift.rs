@@ -0,0 +1,240 @@
+use std::thread;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::edits::{EditPlan, FileDiff};
+use crate::lock::require_switch_allowed;
+use crate::paths::{get_claude_settings_path, get_secrets_path};
+use crate::redact::{mask_token, redacted};
+use crate::settings::read_app_settings;
+use crate::shell::bump_generation;
+use crate::tools::Tool;
+use crate::{parse_env_value, update_env_value};
+
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+const DRIFT_EVENT: &str = "config-drift";
+
+/// A secrets-file export and its copy in the `env` block of the Claude
+/// settings. `always` links are written by every switch, so a missing copy
+/// is drift. The others only matter when the settings carry a copy, since
+/// that copy shadows the shell export.
+struct Link {
+    key: &'static str,
+    secret: bool,
+    always: bool,
+}
+
+const LINKS: [Link; 4] = [
+    Link { key: "CS_BASE_URL", secret: false, always: true },
+    Link { key: "CS_AUTH_TOKEN", secret: true, always: true },
+    Link { key: "ANTHROPIC_BASE_URL", secret: false, always: false },
+    Link { key: "ANTHROPIC_AUTH_TOKEN", secret: true, always: false },
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileDirection {
+    /// Rewrite the tool configs from the active groups in the secrets file.
+    FromSecrets,
+    /// Rewrite the active groups in the secrets file from the tool configs.
+    ToSecrets,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub tool: Tool,
+    pub path: String,
+    pub key: String,
+    /// The value exported by the secrets file. Tokens are masked.
+    pub secrets_value: Option<String>,
+    /// The value in the tool config, or None when the key is missing.
+    pub tool_value: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ConsistencyReport {
+    pub consistent: bool,
+    pub mismatches: Vec<Mismatch>,
+}
+
+#[derive(Serialize)]
+pub struct ReconcileReport {
+    pub committed: bool,
+    pub diffs: Vec<FileDiff>,
+}
+
+/// A mismatch with the unmasked values, as needed to reconcile it.
+struct Drift {
+    link: &'static Link,
+    secrets_value: Option<String>,
+    tool_value: Option<String>,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn parse_settings(content: &str) -> Result<Value, String> {
+    serde_json::from_str(content).map_err(|e| format!("Failed to parse claude settings: {}", redacted(e)))
+}
+
+fn find_drift(secrets: &str, settings: &Value) -> Vec<Drift> {
+    let env = settings.get("env").and_then(Value::as_object);
+
+    LINKS
+        .iter()
+        .filter_map(|link| {
+            let secrets_value = non_empty(parse_env_value(secrets, link.key));
+            let tool_value = env
+                .and_then(|env| env.get(link.key))
+                .and_then(Value::as_str)
+                .map(|v| v.to_string());
+
+            let drifted = match (&secrets_value, &tool_value) {
+                (Some(expected), Some(actual)) => expected != actual,
+                (Some(_), None) => link.always,
+                (None, Some(actual)) => !actual.is_empty(),
+                (None, None) => false,
+            };
+
+            drifted.then_some(Drift { link, secrets_value, tool_value })
+        })
+        .collect()
+}
+
+/// Secrets file and Claude settings, or None when either is missing and
+/// there is nothing to compare.
+fn load(plan: &EditPlan) -> Result<Option<(String, Value)>, String> {
+    let secrets_path = get_secrets_path()?;
+    let settings_path = get_claude_settings_path()?;
+    if !secrets_path.exists() || !settings_path.exists() {
+        return Ok(None);
+    }
+
+    let secrets = plan.read(&secrets_path)?;
+    let settings = parse_settings(&plan.read(&settings_path)?)?;
+    Ok(Some((secrets, settings)))
+}
+
+fn shown(link: &Link, value: &Option<String>) -> Option<String> {
+    if link.secret {
+        value.as_deref().map(mask_token)
+    } else {
+        value.clone()
+    }
+}
+
+/// Compares the active groups in the secrets file with the copies the
+/// switch writes into the Claude settings.
+#[tauri::command]
+pub fn check_consistency() -> Result<ConsistencyReport, String> {
+    let Some((secrets, settings)) = load(&EditPlan::default())? else {
+        return Ok(ConsistencyReport { consistent: true, mismatches: Vec::new() });
+    };
+
+    let path = get_claude_settings_path()?.display().to_string();
+    let mismatches: Vec<Mismatch> = find_drift(&secrets, &settings)
+        .iter()
+        .map(|drift| Mismatch {
+            tool: Tool::Claude,
+            path: path.clone(),
+            key: format!("env.{}", drift.link.key),
+            secrets_value: shown(drift.link, &drift.secrets_value),
+            tool_value: shown(drift.link, &drift.tool_value),
+        })
+        .collect();
+
+    Ok(ConsistencyReport { consistent: mismatches.is_empty(), mismatches })
+}
+
+/// Brings the secrets file and the Claude settings back in sync, copying
+/// every drifted value in `direction`. Keys missing on the source side are
+/// left alone. Like a switch, it is refused while switching is locked, even
+/// as a dry run.
+#[tauri::command]
+pub fn reconcile_consistency(direction: ReconcileDirection, dry_run: bool) -> Result<ReconcileReport, String> {
+    require_switch_allowed()?;
+    let mut plan = EditPlan::default();
+    let Some((mut secrets, mut settings)) = load(&plan)? else {
+        return Ok(ReconcileReport { committed: false, diffs: Vec::new() });
+    };
+
+    let secrets_path = get_secrets_path()?;
+    let settings_path = get_claude_settings_path()?;
+    let mut changed = false;
+
+    for drift in find_drift(&secrets, &settings) {
+        match direction {
+            ReconcileDirection::FromSecrets => {
+                let Some(value) = drift.secrets_value else { continue };
+                let env = settings
+                    .as_object_mut()
+                    .map(|obj| obj.entry("env".to_string()).or_insert_with(|| Value::Object(Map::new())))
+                    .and_then(Value::as_object_mut)
+                    .ok_or_else(|| "Claude settings have a non-object env block".to_string())?;
+                env.insert(drift.link.key.to_string(), Value::String(value));
+                changed = true;
+            }
+            ReconcileDirection::ToSecrets => {
+                let Some(value) = drift.tool_value.filter(|v| !v.is_empty()) else { continue };
+                secrets = update_env_value(&secrets, drift.link.key, &value);
+                changed = true;
+            }
+        }
+    }
+
+    match direction {
+        _ if !changed => {}
+        ReconcileDirection::FromSecrets => {
+            let pretty_json = serde_json::to_string_pretty(&settings)
+                .map_err(|e| format!("Failed to serialize claude settings: {}", e))?;
+            plan.write(&settings_path, pretty_json);
+        }
+        ReconcileDirection::ToSecrets => plan.write(&secrets_path, secrets),
+    }
+
+    let diffs = plan.diffs_for(&[secrets_path, settings_path]);
+    let committed = !dry_run && !diffs.is_empty();
+    if committed {
+        plan.commit()?;
+        if direction == ReconcileDirection::ToSecrets {
+            bump_generation();
+        }
+    }
+
+    Ok(ReconcileReport { committed, diffs })
+}
+
+fn check_interval() -> Option<Duration> {
+    match read_app_settings().ok().and_then(|s| s.drift_check_secs) {
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None => Some(DEFAULT_CHECK_INTERVAL),
+    }
+}
+
+/// Re-runs the consistency check in the background and emits `config-drift`
+/// whenever the set of mismatches changes, including when it clears.
+pub(crate) fn spawn_drift_monitor(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last: Vec<Mismatch> = Vec::new();
+        loop {
+            // Re-read the interval each round so settings changes apply without a restart
+            let Some(interval) = check_interval() else {
+                thread::sleep(DEFAULT_CHECK_INTERVAL);
+                continue;
+            };
+            thread::sleep(interval);
+
+            let Ok(report) = check_consistency() else {
+                continue;
+            };
+            if report.mismatches != last {
+                let _ = app.emit(DRIFT_EVENT, &report);
+                last = report.mismatches;
+            }
+        }
+    });
+}
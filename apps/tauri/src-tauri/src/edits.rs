@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::perms::write_private;
+use crate::redact::redact_secrets;
+
+#[derive(Serialize, Clone)]
+pub struct FileDiff {
+    pub path: String,
+    pub diff: String,
+}
+
+struct PendingFile {
+    path: PathBuf,
+    original: Option<String>,
+    content: String,
+}
+
+/// A set of file edits that is staged in memory, previewed as diffs and
+/// then written all-or-nothing.
+#[derive(Default)]
+pub struct EditPlan {
+    files: Vec<PendingFile>,
+}
+
+impl EditPlan {
+    /// Returns the staged content of `path`, falling back to what is on disk.
+    pub fn read(&self, path: &Path) -> Result<String, String> {
+        if let Some(file) = self.files.iter().find(|f| f.path == path) {
+            return Ok(file.content.clone());
+        }
+        fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    }
+
+    pub fn write(&mut self, path: &Path, content: String) {
+        if let Some(file) = self.files.iter_mut().find(|f| f.path == path) {
+            file.content = content;
+            return;
+        }
+        self.files.push(PendingFile {
+            path: path.to_path_buf(),
+            original: fs::read_to_string(path).ok(),
+            content,
+        });
+    }
+
+    /// Unified diffs for the given paths, skipping files that end up unchanged.
+    /// Diffs are shown in the app and printed by the command line, so tokens
+    /// in them are masked.
+    pub fn diffs_for(&self, paths: &[PathBuf]) -> Vec<FileDiff> {
+        self.files
+            .iter()
+            .filter(|f| paths.contains(&f.path))
+            .filter_map(file_diff)
+            .collect()
+    }
+
+    /// Writes every staged file. If any write fails, files that were already
+    /// written are restored to their original content.
+    pub fn commit(self) -> Result<(), String> {
+        let mut written: Vec<&PendingFile> = Vec::new();
+
+        for file in &self.files {
+            if file.original.as_deref() == Some(file.content.as_str()) {
+                continue;
+            }
+
+            let result = match file.path.parent() {
+                Some(parent) => fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| write_private(&file.path, &file.content));
+
+            if let Err(e) = result {
+                for done in written {
+                    let _ = match &done.original {
+                        Some(original) => write_private(&done.path, original),
+                        None => fs::remove_file(&done.path),
+                    };
+                }
+                return Err(format!("Failed to write {}: {}", file.path.display(), e));
+            }
+
+            written.push(file);
+        }
+
+        Ok(())
+    }
+}
+
+fn file_diff(file: &PendingFile) -> Option<FileDiff> {
+    let original = file.original.as_deref().unwrap_or("");
+    if original == file.content {
+        return None;
+    }
+
+    let path = file.path.display().to_string();
+    let diff = TextDiff::from_lines(original, &file.content)
+        .unified_diff()
+        .header(&path, &path)
+        .to_string();
+
+    Some(FileDiff { path, diff: redact_secrets(&diff) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staged_content_is_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.json");
+        fs::write(&path, "on disk\n").unwrap();
+
+        let mut plan = EditPlan::default();
+        assert_eq!(plan.read(&path).unwrap(), "on disk\n");
+        plan.write(&path, "staged\n".to_string());
+        assert_eq!(plan.read(&path).unwrap(), "staged\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "on disk\n");
+    }
+
+    #[test]
+    fn commit_writes_every_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("a.json");
+        let created = dir.path().join("nested").join("b.json");
+        fs::write(&existing, "old\n").unwrap();
+
+        let mut plan = EditPlan::default();
+        plan.write(&existing, "new\n".to_string());
+        plan.write(&created, "created\n".to_string());
+        plan.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new\n");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "created\n");
+    }
+
+    #[test]
+    fn failed_commit_rolls_back_earlier_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("a.json");
+        let created = dir.path().join("b.json");
+        let blocker = dir.path().join("blocker");
+        fs::write(&existing, "old\n").unwrap();
+        fs::write(&blocker, "a file, not a directory").unwrap();
+
+        let mut plan = EditPlan::default();
+        plan.write(&existing, "new\n".to_string());
+        plan.write(&created, "created\n".to_string());
+        plan.write(&blocker.join("c.json"), "unreachable\n".to_string());
+
+        assert!(plan.commit().is_err());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old\n");
+        assert!(!created.exists());
+    }
+
+    #[test]
+    fn diffs_skip_unchanged_files_and_mask_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let changed = dir.path().join("a.env");
+        let unchanged = dir.path().join("b.env");
+        fs::write(&changed, "export KEY=\"sk-old-token-123456\"\n").unwrap();
+        fs::write(&unchanged, "same\n").unwrap();
+
+        let mut plan = EditPlan::default();
+        plan.write(&changed, "export KEY=\"sk-new-token-654321\"\n".to_string());
+        plan.write(&unchanged, "same\n".to_string());
+        let diffs = plan.diffs_for(&[changed.clone(), unchanged]);
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, changed.display().to_string());
+        assert!(diffs[0].diff.contains("sk-…3456"));
+        assert!(diffs[0].diff.contains("sk-…4321"));
+        assert!(!diffs[0].diff.contains("sk-new-token-654321"));
+    }
+}
@@ -0,0 +1,448 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde_json::Value;
+use toml::Value as TomlValue;
+
+use crate::paths::{
+    get_claude_managed_settings_path, get_claude_settings_path, get_codex_auth_path, get_codex_config_path,
+    get_codex_managed_config_path, get_droid_settings_path, get_opencode_config_path, get_secrets_path,
+};
+use crate::redact::{fingerprint, mask_token};
+use crate::secret_refs::split_export_value;
+use crate::tools::Tool;
+
+const PROCESS_ENV: &str = "process environment";
+const OPENAI_DEFAULT_URL: &str = "https://api.openai.com/v1";
+
+/// A setting together with where it came from. `line` is 1-based and
+/// missing for values that do not come from a file.
+#[derive(Serialize, Clone)]
+pub struct Sourced {
+    pub value: String,
+    pub source: String,
+    pub line: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct EffectiveConfig {
+    pub tool: Tool,
+    pub base_url: Option<Sourced>,
+    /// The winning key, masked. `key_fingerprint` identifies it exactly.
+    pub key: Option<Sourced>,
+    pub key_fingerprint: Option<String>,
+    pub model: Option<Sourced>,
+}
+
+impl EffectiveConfig {
+    fn new(tool: Tool, base_url: Option<Sourced>, key: Option<Sourced>, model: Option<Sourced>) -> Self {
+        let key_fingerprint = key.as_ref().map(|k| fingerprint(&k.value));
+        let key = key.map(|k| Sourced { value: mask_token(&k.value), ..k });
+        EffectiveConfig { tool, base_url, key, key_fingerprint, model }
+    }
+}
+
+/// First line containing `needle`, 1-based.
+fn line_of(content: &str, needle: &str) -> Option<usize> {
+    content.lines().position(|line| line.contains(needle)).map(|i| i + 1)
+}
+
+fn process_env(key: &str) -> Option<Sourced> {
+    env::var(key).ok().filter(|v| !v.is_empty()).map(|value| Sourced {
+        value,
+        source: format!("{} (${})", PROCESS_ENV, key),
+        line: None,
+    })
+}
+
+/// A JSON settings file, kept with its text so values can be located.
+struct JsonFile {
+    path: PathBuf,
+    content: String,
+    json: Value,
+}
+
+impl JsonFile {
+    fn load(path: PathBuf) -> Option<JsonFile> {
+        let content = fs::read_to_string(&path).ok()?;
+        let json = serde_json::from_str(&content).ok()?;
+        Some(JsonFile { path, content, json })
+    }
+
+    fn sourced(&self, value: &Value, key: &str) -> Option<Sourced> {
+        let value = value.as_str().filter(|v| !v.is_empty())?;
+        Some(Sourced {
+            value: value.to_string(),
+            source: self.path.display().to_string(),
+            line: line_of(&self.content, &format!("\"{}\"", key)),
+        })
+    }
+
+    /// The string at `pointer` (a JSON pointer), located by its last segment.
+    fn get(&self, pointer: &str) -> Option<Sourced> {
+        let key = pointer.rsplit('/').next()?;
+        self.sourced(self.json.pointer(pointer)?, key)
+    }
+}
+
+/// A TOML config file, kept with its text so values can be located.
+struct TomlFile {
+    path: PathBuf,
+    content: String,
+    toml: TomlValue,
+}
+
+impl TomlFile {
+    fn load(path: PathBuf) -> Option<TomlFile> {
+        let content = fs::read_to_string(&path).ok()?;
+        let toml = content.parse().ok()?;
+        Some(TomlFile { path, content, toml })
+    }
+
+    /// Line of `key = ...` within `[section]`, or before any section when `section` is None.
+    fn line_in_section(&self, section: Option<&str>, key: &str) -> Option<usize> {
+        let mut current: Option<String> = None;
+        self.content.lines().position(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                current = Some(trimmed.trim_matches(|c| c == '[' || c == ']').trim().to_string());
+                return false;
+            }
+            let is_key = trimmed
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='));
+            is_key && current.as_deref() == section
+        })
+        .map(|i| i + 1)
+    }
+
+    fn get(&self, section: Option<&str>, key: &str) -> Option<Sourced> {
+        let table = match section {
+            Some(section) => section.split('.').try_fold(&self.toml, |t, part| t.get(part))?,
+            None => &self.toml,
+        };
+        let value = table.get(key)?.as_str().filter(|v| !v.is_empty())?;
+        Some(Sourced {
+            value: value.to_string(),
+            source: self.path.display().to_string(),
+            line: self.line_in_section(section, key),
+        })
+    }
+}
+
+/// The active `export KEY=` line in the secrets file, which new shells
+/// source. A reference kept after the token is not part of the value.
+fn secrets_export(path: &Path, key: &str) -> Option<Sourced> {
+    let content = fs::read_to_string(path).ok()?;
+    let prefix = format!("export {}=", key);
+
+    content.lines().enumerate().find_map(|(i, line)| {
+        let (value, _) = split_export_value(line.trim().strip_prefix(&prefix)?);
+        (!value.is_empty()).then(|| Sourced {
+            value: value.to_string(),
+            source: path.display().to_string(),
+            line: Some(i + 1),
+        })
+    })
+}
+
+/// Claude Code layers managed policy over the environment it was started
+/// with, then project-local, project-shared and user settings. The secrets
+/// file comes last: its exports only reach a tool started from a shell that
+/// has not already picked up another value.
+fn resolve_claude(project: Option<&Path>) -> Result<EffectiveConfig, String> {
+    let policy: Vec<JsonFile> = JsonFile::load(get_claude_managed_settings_path()).into_iter().collect();
+    let mut paths = Vec::new();
+    if let Some(project) = project {
+        paths.push(project.join(".claude").join("settings.local.json"));
+        paths.push(project.join(".claude").join("settings.json"));
+    }
+    paths.push(get_claude_settings_path()?);
+    let settings: Vec<JsonFile> = paths.into_iter().filter_map(JsonFile::load).collect();
+
+    Ok(claude_config(&policy, &settings, &get_secrets_path()?, &process_env))
+}
+
+/// Applies Claude Code's precedence to loaded layers, with `env` standing in
+/// for the process environment.
+fn claude_config(
+    policy: &[JsonFile],
+    settings: &[JsonFile],
+    secrets: &Path,
+    env: &dyn Fn(&str) -> Option<Sourced>,
+) -> EffectiveConfig {
+    let env_block = |layers: &[JsonFile], key: &str| layers.iter().find_map(|layer| layer.get(&format!("/env/{}", key)));
+    let env_value = |key: &str| {
+        env_block(policy, key)
+            .or_else(|| env(key))
+            .or_else(|| env_block(settings, key))
+            .or_else(|| secrets_export(secrets, key))
+    };
+
+    let key = env_value("ANTHROPIC_AUTH_TOKEN").or_else(|| env_value("ANTHROPIC_API_KEY"));
+    let model = env_value("ANTHROPIC_MODEL")
+        .or_else(|| policy.iter().chain(settings).find_map(|layer| layer.get("/model")));
+
+    EffectiveConfig::new(Tool::Claude, env_value("ANTHROPIC_BASE_URL"), key, model)
+}
+
+/// Codex picks a provider by `model_provider`, with the managed config taking
+/// precedence over config.toml. The built-in `openai` provider honors
+/// OPENAI_BASE_URL; custom providers name the variable holding their key in
+/// `env_key`, otherwise the key comes from auth.json.
+fn resolve_codex() -> Result<EffectiveConfig, String> {
+    let mut layers = Vec::new();
+    if let Some(managed) = get_codex_managed_config_path() {
+        layers.extend(TomlFile::load(managed));
+    }
+    layers.extend(TomlFile::load(get_codex_config_path()?));
+
+    let top_level = |key: &str| layers.iter().find_map(|layer| layer.get(None, key));
+    let provider = top_level("model_provider")
+        .map(|p| p.value)
+        .unwrap_or_else(|| "openai".to_string());
+    let section = format!("model_providers.{}", provider);
+    let provider_value = |key: &str| layers.iter().find_map(|layer| layer.get(Some(&section), key));
+
+    let base_url = provider_value("base_url").or_else(|| {
+        (provider == "openai").then(|| {
+            process_env("OPENAI_BASE_URL").unwrap_or_else(|| Sourced {
+                value: OPENAI_DEFAULT_URL.to_string(),
+                source: "built-in default".to_string(),
+                line: None,
+            })
+        })
+    });
+
+    let key = match provider_value("env_key") {
+        Some(env_key) => process_env(&env_key.value),
+        None => JsonFile::load(get_codex_auth_path()?)
+            .and_then(|auth| auth.get("/OPENAI_API_KEY"))
+            .or_else(|| process_env("OPENAI_API_KEY")),
+    };
+
+    Ok(EffectiveConfig::new(Tool::Codex, base_url, key, top_level("model")))
+}
+
+fn resolve_droid() -> Result<EffectiveConfig, String> {
+    let Some(settings) = JsonFile::load(get_droid_settings_path()?) else {
+        return Ok(EffectiveConfig::new(Tool::Droid, None, None, None));
+    };
+
+    Ok(EffectiveConfig::new(
+        Tool::Droid,
+        settings.get("/customModels/0/baseUrl"),
+        settings.get("/customModels/0/apiKey"),
+        settings.get("/customModels/0/model"),
+    ))
+}
+
+/// OpenCode substitutes `{env:NAME}` in its config with the variable's value.
+fn expand_opencode_env(value: Sourced) -> Option<Sourced> {
+    let Some(name) = value.value.strip_prefix("{env:").and_then(|v| v.strip_suffix('}')) else {
+        return Some(value);
+    };
+    let resolved = env::var(name).ok().filter(|v| !v.is_empty())?;
+    Some(Sourced {
+        value: resolved,
+        source: format!("{} via ${}", value.source, name),
+        line: value.line,
+    })
+}
+
+/// OpenCode merges project config over $OPENCODE_CONFIG over the global config.
+fn resolve_opencode(project: Option<&Path>) -> Result<EffectiveConfig, String> {
+    let mut paths = Vec::new();
+    if let Some(project) = project {
+        paths.push(project.join("opencode.json"));
+    }
+    if let Some(custom) = env::var_os("OPENCODE_CONFIG").filter(|v| !v.is_empty()) {
+        paths.push(PathBuf::from(custom));
+    }
+    paths.push(get_opencode_config_path()?);
+    let layers: Vec<JsonFile> = paths.into_iter().filter_map(JsonFile::load).collect();
+
+    let value = |pointer: &str| {
+        layers
+            .iter()
+            .find_map(|layer| layer.get(pointer))
+            .and_then(expand_opencode_env)
+    };
+
+    let key = value("/provider/openai/options/apiKey").or_else(|| process_env("OPENAI_API_KEY"));
+
+    Ok(EffectiveConfig::new(
+        Tool::Opencode,
+        value("/provider/openai/options/baseURL"),
+        key,
+        value("/model"),
+    ))
+}
+
+/// What each tool will actually use, after applying its precedence rules to
+/// the files my-switch knows about and the current process environment.
+/// `project` adds that directory's project-level settings.
+#[tauri::command]
+pub fn resolve_effective_config(project: Option<String>) -> Result<Vec<EffectiveConfig>, String> {
+    let project = match project.filter(|p| !p.trim().is_empty()) {
+        Some(dir) => Some(fs::canonicalize(&dir).map_err(|e| format!("Failed to resolve {}: {}", dir, e))?),
+        None => None,
+    };
+    let project = project.as_deref();
+
+    Ok(vec![
+        resolve_claude(project)?,
+        resolve_codex()?,
+        resolve_droid()?,
+        resolve_opencode(project)?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "ANTHROPIC_BASE_URL";
+
+    /// A project, user settings and secrets file, each setting the base URL
+    /// to its own name so the winner is easy to tell.
+    struct Layers {
+        dir: tempfile::TempDir,
+    }
+
+    impl Layers {
+        fn new() -> Layers {
+            let dir = tempfile::tempdir().unwrap();
+            fs::create_dir_all(dir.path().join("project").join(".claude")).unwrap();
+            Layers { dir }
+        }
+
+        fn settings(&self, relative: &str, url: &str) -> &Self {
+            let json = serde_json::json!({ "env": { KEY: url, "ANTHROPIC_AUTH_TOKEN": format!("sk-{}-token-123456", url) } });
+            fs::write(self.dir.path().join(relative), serde_json::to_string_pretty(&json).unwrap()).unwrap();
+            self
+        }
+
+        fn secrets(&self, url: &str) -> &Self {
+            fs::write(self.secrets_path(), format!("export {}=\"{}\"\n", KEY, url)).unwrap();
+            self
+        }
+
+        fn secrets_path(&self) -> PathBuf {
+            self.dir.path().join("secrets")
+        }
+
+        fn resolve(&self, env: &dyn Fn(&str) -> Option<Sourced>) -> EffectiveConfig {
+            let settings: Vec<JsonFile> = [
+                "project/.claude/settings.local.json",
+                "project/.claude/settings.json",
+                "user.json",
+            ]
+            .iter()
+            .filter_map(|relative| JsonFile::load(self.dir.path().join(relative)))
+            .collect();
+            claude_config(&[], &settings, &self.secrets_path(), env)
+        }
+    }
+
+    fn no_env(_: &str) -> Option<Sourced> {
+        None
+    }
+
+    fn process(key: &str) -> Option<Sourced> {
+        (key == KEY).then(|| Sourced {
+            value: "process".to_string(),
+            source: PROCESS_ENV.to_string(),
+            line: None,
+        })
+    }
+
+    fn winner(layers: &Layers, env: &dyn Fn(&str) -> Option<Sourced>) -> String {
+        layers.resolve(env).base_url.unwrap().value
+    }
+
+    #[test]
+    fn process_env_wins_over_every_file() {
+        let layers = Layers::new();
+        layers
+            .settings("project/.claude/settings.local.json", "local")
+            .settings("project/.claude/settings.json", "shared")
+            .settings("user.json", "user")
+            .secrets("secrets");
+        assert_eq!(winner(&layers, &process), "process");
+        assert_eq!(winner(&layers, &no_env), "local");
+    }
+
+    #[test]
+    fn project_shared_wins_over_user_settings() {
+        let layers = Layers::new();
+        layers
+            .settings("project/.claude/settings.json", "shared")
+            .settings("user.json", "user")
+            .secrets("secrets");
+        assert_eq!(winner(&layers, &no_env), "shared");
+    }
+
+    #[test]
+    fn user_settings_win_over_the_secrets_file() {
+        let layers = Layers::new();
+        layers.settings("user.json", "user").secrets("secrets");
+        assert_eq!(winner(&layers, &no_env), "user");
+    }
+
+    #[test]
+    fn the_secrets_file_comes_last() {
+        let layers = Layers::new();
+        layers.secrets("secrets");
+        let url = layers.resolve(&no_env).base_url.unwrap();
+        assert_eq!(url.value, "secrets");
+        assert_eq!(url.source, layers.secrets_path().display().to_string());
+        assert_eq!(url.line, Some(1));
+
+        assert!(Layers::new().resolve(&no_env).base_url.is_none());
+    }
+
+    #[test]
+    fn values_from_files_carry_their_line() {
+        let layers = Layers::new();
+        layers.settings("user.json", "user");
+        let url = layers.resolve(&no_env).base_url.unwrap();
+        assert!(url.source.ends_with("user.json"));
+
+        let content = fs::read_to_string(&url.source).unwrap();
+        assert_eq!(url.line, line_of(&content, &format!("\"{}\"", KEY)));
+        assert!(url.line.is_some());
+    }
+
+    #[test]
+    fn keys_are_reported_masked_with_a_fingerprint() {
+        let layers = Layers::new();
+        layers.settings("user.json", "user");
+        let token = "sk-user-token-123456";
+
+        let config = layers.resolve(&no_env);
+        let key = config.key.unwrap();
+        let key_fingerprint = config.key_fingerprint.unwrap();
+        assert_eq!(key.value, mask_token(token));
+        assert_eq!(key_fingerprint, fingerprint(token));
+        assert!(!key.value.contains(token));
+        assert!(!key_fingerprint.contains(token));
+    }
+
+    #[test]
+    fn secrets_exports_drop_the_kept_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = dir.path().join("secrets");
+        fs::write(
+            &secrets,
+            "#export ANTHROPIC_AUTH_TOKEN=\"cmd:pass show old\"\n\
+             export ANTHROPIC_AUTH_TOKEN=\"sk-ant-resolved-123456\" # my-switch:ref cmd:pass show work\n",
+        )
+        .unwrap();
+
+        let sourced = secrets_export(&secrets, "ANTHROPIC_AUTH_TOKEN").unwrap();
+        assert_eq!(sourced.value, "sk-ant-resolved-123456");
+        assert_eq!(sourced.line, Some(2));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::drift::{reconcile_consistency, ReconcileDirection};
+use crate::lock::require_switch_allowed;
+use crate::paths::{get_enforce_log_path, get_enforcements_path};
+use crate::profiles::{apply_profile_to_tool, resolve_profile};
+use crate::redact::redacted;
+use crate::tools::Tool;
+
+const ENFORCED_EVENT: &str = "config-enforced";
+
+/// A tool that rewrites its config this many times within the window is
+/// fighting the enforcement, so it is stopped rather than looping forever.
+const MAX_CORRECTIONS: usize = 3;
+const CORRECTION_WINDOW: Duration = Duration::from_secs(60);
+
+/// Recent corrections, for the loop guard.
+static RECENT: Mutex<Vec<(Tool, Instant)>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Enforcement {
+    pub tool: Tool,
+    /// A profile selector, as accepted by `my-switch exec --profile`.
+    pub profile: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Correction {
+    /// Unix seconds.
+    pub at: u64,
+    pub tool: Tool,
+    pub profile: String,
+    pub files: Vec<String>,
+    pub error: Option<String>,
+}
+
+fn read_enforcements() -> Result<Vec<Enforcement>, String> {
+    let path = get_enforcements_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read enforcements: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse enforcements: {}", redacted(e)))
+}
+
+fn write_enforcements(enforcements: &[Enforcement]) -> Result<(), String> {
+    let path = get_enforcements_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create enforcements directory: {}", e))?;
+    }
+
+    let pretty_json = serde_json::to_string_pretty(enforcements)
+        .map_err(|e| format!("Failed to serialize enforcements: {}", e))?;
+
+    fs::write(&path, pretty_json)
+        .map_err(|e| format!("Failed to write enforcements: {}", e))
+}
+
+/// Appends to the correction log. Best effort, like the generation stamp:
+/// a correction that cannot be logged has still been made.
+fn log_correction(correction: &Correction) {
+    let Ok(path) = get_enforce_log_path() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(correction) else {
+        return;
+    };
+
+    let _ = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Records a correction for `tool` and returns whether it is still under the limit.
+fn within_limit(tool: Tool) -> bool {
+    let Ok(mut recent) = RECENT.lock() else {
+        return false;
+    };
+    recent.retain(|(_, at)| at.elapsed() < CORRECTION_WINDOW);
+    recent.push((tool, Instant::now()));
+    recent.iter().filter(|(t, _)| *t == tool).count() <= MAX_CORRECTIONS
+}
+
+/// Writes the profile to the tool. For Claude Code this includes the `env`
+/// copies in its settings, which it may drop when it rewrites the file.
+fn enforce(enforcement: &Enforcement) -> Result<Vec<String>, String> {
+    let profile = resolve_profile(&enforcement.profile)?;
+    let mut files: Vec<String> = apply_profile_to_tool(enforcement.tool, &profile)?
+        .into_iter()
+        .map(|d| d.path)
+        .collect();
+
+    if enforcement.tool == Tool::Claude {
+        let report = reconcile_consistency(ReconcileDirection::FromSecrets, false)?;
+        files.extend(report.diffs.into_iter().map(|d| d.path));
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn stop(tool: Tool) -> Result<(), String> {
+    let mut enforcements = read_enforcements()?;
+    enforcements.retain(|e| e.tool != tool);
+    write_enforcements(&enforcements)
+}
+
+/// Re-applies the enforced profile after `tool` changed its files. The
+/// watcher leaves out files that still hold what the app wrote, so switches
+/// and saves made in the app, and these corrections, are not reverted.
+pub(crate) fn reapply(app: &AppHandle, tool: Tool) {
+    let Ok(enforcements) = read_enforcements() else {
+        return;
+    };
+    let Some(enforcement) = enforcements.into_iter().find(|e| e.tool == tool) else {
+        return;
+    };
+
+    let (files, error) = match enforce(&enforcement) {
+        Ok(files) if files.is_empty() => return,
+        Ok(files) => (files, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+
+    let error = if within_limit(tool) {
+        error
+    } else {
+        let _ = stop(tool);
+        Some(format!(
+            "{} kept rewriting its config, so enforcement was stopped",
+            tool.display_name()
+        ))
+    };
+
+    let correction = Correction {
+        at: now(),
+        tool,
+        profile: enforcement.profile,
+        files,
+        // The log outlives the session, so it must not keep a token a tool echoed
+        error: error.map(redacted),
+    };
+    log_correction(&correction);
+    let _ = app.emit(ENFORCED_EVENT, &correction);
+}
+
+/// Points tools that are already enforced at a newly applied profile, so
+/// enforcement does not undo a switch made in the app.
+pub(crate) fn retarget(tools: impl IntoIterator<Item = Tool>, profile: &str) {
+    let Ok(mut enforcements) = read_enforcements() else {
+        return;
+    };
+
+    let mut changed = false;
+    for tool in tools {
+        if let Some(enforcement) = enforcements.iter_mut().find(|e| e.tool == tool) {
+            enforcement.profile = profile.to_string();
+            changed = true;
+        }
+    }
+
+    if changed {
+        let _ = write_enforcements(&enforcements);
+    }
+}
+
+#[tauri::command]
+pub fn list_enforcements() -> Result<Vec<Enforcement>, String> {
+    read_enforcements()
+}
+
+/// Holds `tool` to `profile`, applying it now and again whenever the tool
+/// rewrites its config.
+#[tauri::command]
+pub fn enforce_profile(tool: Tool, profile: String) -> Result<Vec<Enforcement>, String> {
+    require_switch_allowed()?;
+    let enforcement = Enforcement { tool, profile };
+    enforce(&enforcement)?;
+
+    let mut enforcements = read_enforcements()?;
+    enforcements.retain(|e| e.tool != tool);
+    enforcements.push(enforcement);
+    write_enforcements(&enforcements)?;
+
+    Ok(enforcements)
+}
+
+#[tauri::command]
+pub fn stop_enforcing(tool: Tool) -> Result<Vec<Enforcement>, String> {
+    require_switch_allowed()?;
+    stop(tool)?;
+    read_enforcements()
+}
+
+/// The most recent corrections, newest first.
+#[tauri::command]
+pub fn enforcement_log(limit: usize) -> Result<Vec<Correction>, String> {
+    let path = get_enforce_log_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read enforcement log: {}", e))?;
+
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
+}
@@ -0,0 +1,245 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::json;
+
+use crate::bootstrap::CODEX_CONFIG_TEMPLATE;
+use crate::paths::{get_codex_auth_path, get_codex_config_path};
+use crate::perms::write_private;
+use crate::profiles::{render_codex_model, resolve_profile, Protocol, ProviderProfile};
+use crate::urls::{normalize_url, UrlStyle};
+use crate::{render_codex_auth, render_codex_config};
+
+/// A private temporary directory holding per-command tool configs. It is
+/// removed when dropped, so credentials do not outlive the command.
+struct Overlay {
+    dir: PathBuf,
+}
+
+impl Overlay {
+    fn create() -> Result<Overlay, String> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let dir = env::temp_dir().join(format!("my-switch-exec-{}-{}", std::process::id(), nanos));
+
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(&dir)
+            .map_err(|e| format!("Failed to create overlay directory: {}", e))?;
+
+        Ok(Overlay { dir })
+    }
+
+    fn write(&self, relative: &str, content: &str) -> Result<PathBuf, String> {
+        let path = self.dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        write_private(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Environment variables that point Anthropic- and OpenAI-compatible tools
+/// at the profile. A `cs:` group sets the `CS_*` variables and every other
+/// Anthropic profile the `ANTHROPIC_*` ones, so a profile never overrides
+/// the other family.
+pub(crate) fn profile_env(profile: &ProviderProfile) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+
+    if profile.protocol.serves(Protocol::Anthropic) {
+        let url = normalize_url(&profile.base_url, UrlStyle::Anthropic)?.url;
+        if profile.id.starts_with("cs:") {
+            vars.push(("CS_BASE_URL".to_string(), url));
+            vars.push(("CS_AUTH_TOKEN".to_string(), profile.api_key.expose().to_string()));
+        } else {
+            vars.push(("ANTHROPIC_BASE_URL".to_string(), url));
+            vars.push(("ANTHROPIC_AUTH_TOKEN".to_string(), profile.api_key.expose().to_string()));
+            if let Some(model) = &profile.model {
+                vars.push(("ANTHROPIC_MODEL".to_string(), model.clone()));
+            }
+        }
+    }
+
+    if profile.protocol.serves(Protocol::Openai) {
+        let url = normalize_url(&profile.base_url, UrlStyle::Openai)?.url;
+        vars.push(("OPENAI_BASE_URL".to_string(), url));
+        vars.push(("OPENAI_API_KEY".to_string(), profile.api_key.expose().to_string()));
+    }
+
+    Ok(vars)
+}
+
+/// Links everything in the real Codex home except the files the overlay
+/// replaces, so sessions and history keep landing in the usual place.
+#[cfg(unix)]
+fn link_codex_home(real_home: &Path, overlay_home: &Path) {
+    let Ok(entries) = fs::read_dir(real_home) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if name == "config.toml" || name == "auth.json" {
+            continue;
+        }
+        let _ = std::os::unix::fs::symlink(entry.path(), overlay_home.join(&name));
+    }
+}
+
+#[cfg(not(unix))]
+fn link_codex_home(_real_home: &Path, _overlay_home: &Path) {}
+
+/// Writes the file-based tool configs for the profile into the overlay and
+/// returns the variables that make the tools read them.
+fn write_overlay(overlay: &Overlay, profile: &ProviderProfile) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    if !profile.protocol.serves(Protocol::Openai) {
+        return Ok(vars);
+    }
+
+    let url = normalize_url(&profile.base_url, UrlStyle::Openai)?.url;
+
+    // Codex reads credentials from $CODEX_HOME/auth.json
+    let real_config = get_codex_config_path()?;
+    let base_config = fs::read_to_string(&real_config).unwrap_or_else(|_| CODEX_CONFIG_TEMPLATE.to_string());
+    let mut codex_config = render_codex_config(&base_config, &url);
+    if let Some(model) = &profile.model {
+        codex_config = render_codex_model(&codex_config, model);
+    }
+    let config_path = overlay.write("codex/config.toml", &codex_config)?;
+
+    let base_auth = fs::read_to_string(get_codex_auth_path()?).unwrap_or_else(|_| "{}".to_string());
+    overlay.write("codex/auth.json", &render_codex_auth(&base_auth, profile.api_key.expose())?)?;
+
+    if let (Some(real_home), Some(overlay_home)) = (real_config.parent(), config_path.parent()) {
+        link_codex_home(real_home, overlay_home);
+        vars.push(("CODEX_HOME".to_string(), overlay_home.display().to_string()));
+    }
+
+    // OpenCode merges $OPENCODE_CONFIG over its global config
+    let mut opencode = json!({
+        "$schema": "https://opencode.ai/config.json",
+        "provider": {
+            "openai": {
+                "options": {
+                    "baseURL": url,
+                    "apiKey": profile.api_key.expose()
+                }
+            }
+        }
+    });
+    if let Some(model) = &profile.model {
+        opencode["model"] = json!(format!("openai/{}", model));
+    }
+    let opencode_content = serde_json::to_string_pretty(&opencode)
+        .map_err(|e| format!("Failed to serialize opencode config: {}", e))?;
+    let opencode_path = overlay.write("opencode.json", &opencode_content)?;
+    vars.push(("OPENCODE_CONFIG".to_string(), opencode_path.display().to_string()));
+
+    Ok(vars)
+}
+
+#[cfg(unix)]
+fn exit_code(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|s| 128 + s))
+        .unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: ExitStatus) -> i32 {
+    status.code().unwrap_or(1)
+}
+
+/// Runs `command` with the profile's credentials without touching the global
+/// tool configs, and returns the command's exit code.
+pub(crate) fn exec_with_profile(selector: &str, command: &[String]) -> Result<i32, String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "No command given".to_string())?;
+
+    let profile = resolve_profile(selector)?;
+    let overlay = Overlay::create()?;
+
+    let mut vars = profile_env(&profile)?;
+    vars.extend(write_overlay(&overlay, &profile)?);
+
+    // The child handles Ctrl-C itself; we only need to outlive it to clean up
+    let _ = ctrlc::set_handler(|| {});
+
+    let sets_anthropic = vars.iter().any(|(key, _)| key == "ANTHROPIC_AUTH_TOKEN");
+    let mut child = Command::new(program);
+    child.args(args).envs(vars);
+    if sets_anthropic {
+        // A stray API key would be sent alongside the profile's token
+        child.env_remove("ANTHROPIC_API_KEY");
+    }
+
+    let status = child
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    drop(overlay);
+    Ok(exit_code(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::Secret;
+
+    fn profile(id: &str, protocol: Protocol) -> ProviderProfile {
+        ProviderProfile {
+            id: id.to_string(),
+            name: id.to_string(),
+            base_url: "https://gateway.example.com".to_string(),
+            api_key: Secret::new("sk-test-token-123456".to_string()),
+            protocol,
+            model: None,
+            key_fingerprint: None,
+        }
+    }
+
+    fn keys(vars: &[(String, String)]) -> Vec<&str> {
+        vars.iter().map(|(k, _)| k.as_str()).collect()
+    }
+
+    #[test]
+    fn cs_groups_set_only_cs_variables() {
+        let vars = profile_env(&profile("cs:0", Protocol::Anthropic)).unwrap();
+        assert_eq!(keys(&vars), ["CS_BASE_URL", "CS_AUTH_TOKEN"]);
+    }
+
+    #[test]
+    fn anthropic_profiles_set_only_anthropic_variables() {
+        for id in ["anthropic:1", "work"] {
+            let vars = profile_env(&profile(id, Protocol::Anthropic)).unwrap();
+            assert_eq!(keys(&vars), ["ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN"]);
+        }
+    }
+
+    #[test]
+    fn openai_profiles_set_openai_variables() {
+        let vars = profile_env(&profile("work", Protocol::Openai)).unwrap();
+        assert_eq!(keys(&vars), ["OPENAI_BASE_URL", "OPENAI_API_KEY"]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::env;
+
+use crate::exec::profile_env;
+use crate::profiles::resolve_profile;
+
+/// Every variable a profile can set, so leftovers from another profile can
+/// be unset. ANTHROPIC_API_KEY is never set, but would shadow the token.
+const MANAGED_VARS: [&str; 8] = [
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_MODEL",
+    "ANTHROPIC_API_KEY",
+    "CS_BASE_URL",
+    "CS_AUTH_TOKEN",
+    "OPENAI_BASE_URL",
+    "OPENAI_API_KEY",
+];
+
+/// Single-quotes a value for sh-compatible shells.
+pub(crate) fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnvFormat {
+    Posix,
+    Fish,
+    Powershell,
+    Dotenv,
+}
+
+impl EnvFormat {
+    pub fn parse(name: &str) -> Result<EnvFormat, String> {
+        match name {
+            "zsh" | "bash" | "sh" => Ok(EnvFormat::Posix),
+            "fish" => Ok(EnvFormat::Fish),
+            "powershell" | "pwsh" => Ok(EnvFormat::Powershell),
+            "dotenv" => Ok(EnvFormat::Dotenv),
+            _ => Err(format!("Unsupported shell: {}", name)),
+        }
+    }
+
+    /// Guesses the dialect from $SHELL, falling back to POSIX syntax.
+    pub fn detect() -> EnvFormat {
+        env::var("SHELL")
+            .ok()
+            .and_then(|shell| shell.rsplit('/').next().map(|s| s.to_string()))
+            .and_then(|name| EnvFormat::parse(&name).ok())
+            .unwrap_or(EnvFormat::Posix)
+    }
+
+    fn export(self, key: &str, value: &str) -> String {
+        match self {
+            EnvFormat::Posix => format!("export {}={}", key, posix_quote(value)),
+            EnvFormat::Fish => format!(
+                "set -gx {} '{}'",
+                key,
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            EnvFormat::Powershell => format!("$env:{} = '{}'", key, value.replace('\'', "''")),
+            EnvFormat::Dotenv => format!(
+                "{}=\"{}\"",
+                key,
+                value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            ),
+        }
+    }
+
+    /// dotenv files have no way to unset a variable, so they get None.
+    fn unset(self, key: &str) -> Option<String> {
+        match self {
+            EnvFormat::Posix => Some(format!("unset {}", key)),
+            EnvFormat::Fish => Some(format!("set -e {}", key)),
+            EnvFormat::Powershell => Some(format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", key)),
+            EnvFormat::Dotenv => None,
+        }
+    }
+}
+
+/// Renders the profile's variables in the given dialect. With `unset_leftovers`,
+/// managed variables that are set in the current environment but not by this
+/// profile are unset first, since they belong to a previously active profile.
+pub(crate) fn render_profile_env(
+    selector: &str,
+    format: EnvFormat,
+    unset_leftovers: bool,
+) -> Result<String, String> {
+    let profile = resolve_profile(selector)?;
+    let vars = profile_env(&profile)?;
+    let mut lines = Vec::new();
+
+    if unset_leftovers {
+        for key in MANAGED_VARS {
+            let set_by_profile = vars.iter().any(|(k, _)| k == key);
+            if !set_by_profile && env::var_os(key).is_some() {
+                lines.extend(format.unset(key));
+            }
+        }
+    }
+
+    for (key, value) in &vars {
+        lines.push(format.export(key, value));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWKWARD: &str = "it's a \"quoted\" $HOME \\ value";
+
+    #[test]
+    fn formats_are_parsed_by_shell_name() {
+        assert_eq!(EnvFormat::parse("zsh").unwrap(), EnvFormat::Posix);
+        assert_eq!(EnvFormat::parse("pwsh").unwrap(), EnvFormat::Powershell);
+        assert!(EnvFormat::parse("tcsh").is_err());
+    }
+
+    #[test]
+    fn posix_quoting_keeps_the_value_literal() {
+        assert_eq!(posix_quote("plain"), "'plain'");
+        assert_eq!(posix_quote("it's"), "'it'\\''s'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn posix_quoting_survives_the_shell() {
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", posix_quote(AWKWARD)))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), AWKWARD);
+    }
+
+    #[test]
+    fn each_dialect_escapes_its_quotes() {
+        assert_eq!(
+            EnvFormat::Fish.export("KEY", AWKWARD),
+            "set -gx KEY 'it\\'s a \"quoted\" $HOME \\\\ value'"
+        );
+        assert_eq!(
+            EnvFormat::Powershell.export("KEY", AWKWARD),
+            "$env:KEY = 'it''s a \"quoted\" $HOME \\ value'"
+        );
+        assert_eq!(
+            EnvFormat::Dotenv.export("KEY", "a \"b\"\nc\\d"),
+            "KEY=\"a \\\"b\\\"\\nc\\\\d\""
+        );
+    }
+
+    #[test]
+    fn dotenv_cannot_unset() {
+        assert_eq!(EnvFormat::Posix.unset("KEY").as_deref(), Some("unset KEY"));
+        assert_eq!(EnvFormat::Dotenv.unset("KEY"), None);
+    }
+}
@@ -0,0 +1,322 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use crate::bindings::dotenv_files;
+use crate::paths::{
+    expand_home, get_claude_settings_path, get_codex_auth_path, get_droid_settings_path,
+    get_opencode_config_path, get_profiles_path, get_secrets_path, get_vault_path,
+};
+use crate::profiles::{read_profiles, Protocol};
+use crate::projects::{known_projects, managed_local_settings};
+use crate::redact::{fingerprint, mask_token};
+use crate::secret::Secret;
+use crate::secret_refs::{is_reference, resolve_token, runs_command};
+use crate::settings::read_app_settings;
+use crate::urls::UrlStyle;
+use crate::{load_codex_config, load_droid_config, load_opencode_config, parse_config_groups};
+
+/// Shorter values match too much unrelated text to be worth reporting.
+const MIN_TOKEN_LEN: usize = 12;
+/// Larger files are logs, databases or build output rather than places a
+/// token gets pasted.
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// Directories that are never worth descending into.
+const SKIPPED_DIRS: [&str; 8] = [".git", "node_modules", "target", "dist", "build", ".venv", "__pycache__", ".cache"];
+/// Shell and REPL history, relative to the home directory.
+const HISTORY_FILES: [&str; 5] = [
+    "~/.zsh_history",
+    "~/.bash_history",
+    "~/.local/share/fish/fish_history",
+    "~/.python_history",
+    "~/.node_repl_history",
+];
+
+pub(crate) struct KnownToken {
+    pub token: Secret,
+    pub source: String,
+    /// Where the token is used, for checking it.
+    pub base_url: String,
+    pub style: UrlStyle,
+}
+
+#[derive(Serialize)]
+pub struct LeakHit {
+    pub path: String,
+    /// 1-based.
+    pub line: usize,
+    pub masked: String,
+    pub fingerprint: String,
+    /// Where the token is managed, e.g. `profile work` or `cs group 1`.
+    pub source: String,
+}
+
+#[derive(Serialize)]
+pub struct LeakReport {
+    pub scanned_files: usize,
+    pub hits: Vec<LeakHit>,
+    /// Sources whose token was not searched for, because it sits behind a
+    /// command, a password manager or a locked vault.
+    pub skipped: Vec<String>,
+}
+
+/// Every token my-switch manages, once per place it is managed, and the
+/// sources whose token could not be looked up. References are resolved only
+/// when that does not run anything, so a scan never prompts for a password
+/// manager.
+pub(crate) fn known_tokens() -> (Vec<KnownToken>, Vec<String>) {
+    let mut candidates: Vec<KnownToken> = Vec::new();
+
+    let secrets = get_secrets_path().ok().and_then(|p| fs::read_to_string(p).ok());
+    if let Some(content) = secrets {
+        for (family, url_key, token_key) in [
+            ("cs", "CS_BASE_URL", "CS_AUTH_TOKEN"),
+            ("anthropic", "ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN"),
+        ] {
+            for (i, group) in parse_config_groups(&content, url_key, token_key).into_iter().enumerate() {
+                candidates.push(KnownToken {
+                    token: group.auth_token,
+                    source: format!("{} group {}", family, i),
+                    base_url: group.base_url,
+                    style: UrlStyle::Anthropic,
+                });
+            }
+        }
+    }
+
+    for profile in read_profiles().unwrap_or_default() {
+        candidates.push(KnownToken {
+            token: profile.api_key,
+            source: format!("profile {}", profile.name),
+            base_url: profile.base_url,
+            style: if profile.protocol == Protocol::Openai { UrlStyle::Openai } else { UrlStyle::Anthropic },
+        });
+    }
+
+    for (tool, config) in [
+        ("Codex", load_codex_config()),
+        ("Droid", load_droid_config()),
+        ("OpenCode", load_opencode_config()),
+    ] {
+        if let Ok(config) = config {
+            candidates.push(KnownToken {
+                token: config.api_key,
+                source: format!("{} config", tool),
+                base_url: config.base_url,
+                style: UrlStyle::Openai,
+            });
+        }
+    }
+
+    let mut tokens: Vec<KnownToken> = Vec::new();
+    let mut skipped = Vec::new();
+    for mut known in candidates {
+        if is_reference(known.token.expose()) {
+            let resolved = if runs_command(known.token.expose()) { None } else { resolve_token(known.token.expose()).ok() };
+            let Some(token) = resolved else {
+                skipped.push(known.source);
+                continue;
+            };
+            known.token = token.into();
+        }
+
+        if known.token.expose().chars().count() >= MIN_TOKEN_LEN {
+            tokens.push(known);
+        }
+    }
+    (tokens, skipped)
+}
+
+/// Files that hold tokens by design and are not leaks: the tool configs, the
+/// app's own stores, the git-ignored `settings.local.json` files my-switch
+/// applies profiles to and the `.env` files of directory bindings. A shared
+/// project `settings.json` is not among them, since it gets committed.
+pub(crate) fn managed_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = [
+        get_secrets_path(),
+        get_claude_settings_path(),
+        get_codex_auth_path(),
+        get_droid_settings_path(),
+        get_opencode_config_path(),
+        get_profiles_path(),
+        get_vault_path(),
+    ]
+    .into_iter()
+    .filter_map(Result::ok)
+    .chain(managed_local_settings(&known_projects()))
+    .chain(dotenv_files())
+    .collect();
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Shell history, the projects Claude Code knows and the extra locations
+/// from the app settings.
+fn default_targets() -> Vec<String> {
+    let mut targets: Vec<String> = HISTORY_FILES.iter().map(|f| f.to_string()).collect();
+    targets.extend(known_projects().into_iter().map(|p| p.display().to_string()));
+    if let Ok(settings) = read_app_settings() {
+        targets.extend(settings.leak_scan_paths);
+    }
+    targets
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    // Links are not followed, so a scan cannot loop or wander out of the target
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return;
+    };
+
+    if metadata.is_file() {
+        if metadata.len() <= MAX_FILE_SIZE {
+            files.push(path.to_path_buf());
+        }
+        return;
+    }
+    if !metadata.is_dir() {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if SKIPPED_DIRS.iter().any(|d| name == *d) {
+            continue;
+        }
+        collect_files(&entry.path(), files);
+    }
+}
+
+fn scan_file(path: &Path, tokens: &[KnownToken], hits: &mut Vec<LeakHit>) {
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+    // History files are not always valid UTF-8
+    let content = String::from_utf8_lossy(&bytes);
+
+    for (i, line) in content.lines().enumerate() {
+        for known in tokens.iter().filter(|t| line.contains(t.token.expose())) {
+            hits.push(LeakHit {
+                path: path.display().to_string(),
+                line: i + 1,
+                masked: mask_token(known.token.expose()),
+                fingerprint: fingerprint(known.token.expose()),
+                source: known.source.clone(),
+            });
+        }
+    }
+}
+
+/// Searches files and directories for the tokens my-switch manages. Without
+/// `paths`, shell history, known projects and the configured extra locations
+/// are searched. Hits report the masked token, never the token itself.
+/// Walking projects can take a while, so it runs off the main thread.
+#[tauri::command(async)]
+pub fn scan_for_leaks(paths: Option<Vec<String>>) -> Result<LeakReport, String> {
+    let (mut tokens, skipped) = known_tokens();
+    // The same token is often managed in several places; report it once
+    let mut seen = Vec::new();
+    tokens.retain(|t| {
+        let new = !seen.contains(&t.token);
+        seen.push(t.token.clone());
+        new
+    });
+    let targets = paths.unwrap_or_else(default_targets);
+    let (scanned_files, hits) = scan(&targets, &tokens, &managed_files())?;
+
+    Ok(LeakReport {
+        scanned_files,
+        hits,
+        skipped,
+    })
+}
+
+/// Searches `targets` for `tokens`, leaving out the `managed` files, and
+/// returns how many files were searched and what was found.
+fn scan(targets: &[String], tokens: &[KnownToken], managed: &[PathBuf]) -> Result<(usize, Vec<LeakHit>), String> {
+    let mut files = Vec::new();
+    for target in targets {
+        collect_files(&expand_home(target)?, &mut files);
+    }
+    files.sort();
+    files.dedup();
+    files.retain(|f| !managed.contains(f));
+
+    let mut hits = Vec::new();
+    if !tokens.is_empty() {
+        for file in &files {
+            scan_file(file, tokens, &mut hits);
+        }
+    }
+    Ok((files.len(), hits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "sk-ant-REDACTED";
+
+    fn known() -> Vec<KnownToken> {
+        vec![KnownToken {
+            token: Secret::new(TOKEN),
+            source: "profile work".to_string(),
+            base_url: "https://api.anthropic.com".to_string(),
+            style: UrlStyle::Anthropic,
+        }]
+    }
+
+    fn project_with_token(file: &str) -> tempfile::TempDir {
+        let project = tempfile::tempdir().unwrap();
+        let settings = project.path().join(".claude");
+        fs::create_dir_all(&settings).unwrap();
+        fs::write(
+            settings.join(file),
+            serde_json::json!({ "env": { "ANTHROPIC_AUTH_TOKEN": TOKEN } }).to_string(),
+        )
+        .unwrap();
+        project
+    }
+
+    #[test]
+    fn tokens_in_shared_project_settings_are_reported() {
+        let project = project_with_token("settings.json");
+        let managed = managed_local_settings(&[project.path().to_path_buf()]);
+        assert!(managed.is_empty());
+
+        let targets = [project.path().display().to_string()];
+        let (scanned, hits) = scan(&targets, &known(), &managed).unwrap();
+        assert_eq!(scanned, 1);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].path.ends_with("settings.json"));
+        assert_eq!(hits[0].masked, mask_token(TOKEN));
+    }
+
+    #[test]
+    fn local_settings_outside_git_are_managed() {
+        let project = project_with_token("settings.local.json");
+        let managed = managed_local_settings(&[project.path().to_path_buf()]);
+        assert_eq!(managed, [project.path().join(".claude").join("settings.local.json")]);
+
+        let targets = [project.path().display().to_string()];
+        let (scanned, hits) = scan(&targets, &known(), &managed).unwrap();
+        assert_eq!(scanned, 0);
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn hits_never_carry_the_token() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), format!("first\nexport KEY={}\n", TOKEN)).unwrap();
+
+        let (_, hits) = scan(&[dir.path().display().to_string()], &known(), &[]).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].line, 2);
+        assert!(!hits[0].masked.contains(TOKEN));
+        assert_eq!(hits[0].fingerprint, fingerprint(TOKEN));
+    }
+}
@@ -1,226 +1,237 @@
 use std::fs;
-use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use toml::Value as TomlValue;
 
+mod bindings;
+mod bootstrap;
+mod bundles;
+mod cli;
+mod detect;
+mod drift;
+mod edits;
+mod effective;
+mod enforce;
+mod exec;
+mod exports;
+mod leaks;
+mod lock;
+mod paths;
+mod perms;
+mod profiles;
+mod projects;
+mod redact;
+mod rotate;
+mod secret;
+mod secret_refs;
+mod settings;
+mod shell;
+mod tools;
+mod urls;
+mod validate;
+mod vault;
+mod watch;
+
+use paths::{
+    get_claude_settings_path, get_codex_auth_path, get_codex_config_path, get_droid_settings_path,
+    get_opencode_config_path, get_secrets_path,
+};
+use lock::{require_app_unlocked, require_switch_allowed};
+use perms::write_private;
+use profiles::Protocol;
+use secret::Secret;
+use redact::{redacted, unmask, value_fingerprint};
+use secret_refs::{export_value, is_reference, split_export_value};
+use urls::{normalize_url, UrlStyle};
+use validate::checked_token;
+use watch::note_self_write;
+
+pub use cli::run_cli;
+
 #[derive(Serialize, Deserialize)]
 pub struct EnvConfig {
     pub cs_base_url: String,
-    pub cs_auth_token: String,
+    pub cs_auth_token: Secret,
+    /// Identifies the token, which read commands send masked.
+    #[serde(skip_deserializing)]
+    pub token_fingerprint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CsConfigGroup {
     pub base_url: String,
-    pub auth_token: String,
+    pub auth_token: Secret,
     pub active: bool,
+    #[serde(skip_deserializing)]
+    pub token_fingerprint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AnthropicConfigGroup {
     pub base_url: String,
-    pub auth_token: String,
+    pub auth_token: Secret,
     pub active: bool,
+    #[serde(skip_deserializing)]
+    pub token_fingerprint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct AnthropicConfig {
     pub base_url: String,
-    pub auth_token: String,
+    pub auth_token: Secret,
+    #[serde(skip_deserializing)]
+    pub token_fingerprint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CodexConfig {
     pub base_url: String,
-    pub api_key: String,
-}
-
-fn get_secrets_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".zshrc_secrets")
+    pub api_key: Secret,
+    /// Read commands send the key masked; this identifies it.
+    #[serde(skip_deserializing)]
+    pub token_fingerprint: Option<String>,
 }
 
-fn get_claude_settings_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".claude")
-        .join("settings.json")
-}
-
-fn get_codex_config_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".codex")
-        .join("config.toml")
-}
-
-fn get_codex_auth_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".codex")
-        .join("auth.json")
+/// The exported value and the reference it was resolved from, if any.
+fn parse_env_export(content: &str, key: &str) -> (String, Option<String>) {
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(raw) = line.strip_prefix(&format!("export {}=", key)) {
+            let (value, reference) = split_export_value(raw);
+            return (value.to_string(), reference.map(str::to_string));
+        }
+    }
+    (String::new(), None)
 }
 
-fn get_droid_settings_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".factory")
-        .join("settings.json")
+fn parse_env_value(content: &str, key: &str) -> String {
+    parse_env_export(content, key).0
 }
 
-fn get_opencode_config_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join(".config")
-        .join("opencode")
-        .join("opencode.json")
+/// The value to show and edit: the reference an export was resolved from,
+/// or else the value itself.
+fn parse_env_field(content: &str, key: &str) -> String {
+    let (value, reference) = parse_env_export(content, key);
+    reference.unwrap_or(value)
 }
 
-fn parse_env_value(content: &str, key: &str) -> String {
-    for line in content.lines() {
-        let line = line.trim();
-        if line.starts_with(&format!("export {}=", key)) {
-            let value = line
-                .strip_prefix(&format!("export {}=", key))
-                .unwrap_or("");
-            return value.trim_matches('"').to_string();
-        }
-    }
-    String::new()
+fn update_env_value(content: &str, key: &str, new_value: &str) -> String {
+    update_env_export(content, key, new_value, None)
 }
 
-fn update_env_value(content: &str, key: &str, new_value: &str) -> String {
+/// Sets an export, keeping `reference` after the value when it was resolved from one.
+fn update_env_export(content: &str, key: &str, new_value: &str, reference: Option<&str>) -> String {
     let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
     let mut found = false;
+    let export = format!("export {}={}", key, export_value(new_value, reference));
 
     for line in lines.iter_mut() {
         if line.trim().starts_with(&format!("export {}=", key)) {
-            *line = format!("export {}=\"{}\"", key, new_value);
+            *line = export.clone();
             found = true;
             break;
         }
     }
 
     if !found {
-        lines.push(format!("export {}=\"{}\"", key, new_value));
+        lines.push(export);
     }
 
     lines.join("\n")
 }
 
-fn update_claude_settings(config: &EnvConfig) -> Result<(), String> {
-    let path = get_claude_settings_path();
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read claude settings: {}", e))?;
-
-    let mut json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse claude settings: {}", e))?;
+fn render_claude_settings(content: &str, config: &EnvConfig) -> Result<String, String> {
+    let mut json: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse claude settings: {}", redacted(e)))?;
 
     if let Some(env) = json.get_mut("env") {
         if let Some(env_obj) = env.as_object_mut() {
             env_obj.insert("CS_BASE_URL".to_string(), Value::String(config.cs_base_url.clone()));
-            env_obj.insert("CS_AUTH_TOKEN".to_string(), Value::String(config.cs_auth_token.clone()));
+            env_obj.insert("CS_AUTH_TOKEN".to_string(), Value::String(config.cs_auth_token.expose().to_string()));
         }
     } else {
         if let Some(obj) = json.as_object_mut() {
             let mut env_obj = serde_json::Map::new();
             env_obj.insert("CS_BASE_URL".to_string(), Value::String(config.cs_base_url.clone()));
-            env_obj.insert("CS_AUTH_TOKEN".to_string(), Value::String(config.cs_auth_token.clone()));
+            env_obj.insert("CS_AUTH_TOKEN".to_string(), Value::String(config.cs_auth_token.expose().to_string()));
             obj.insert("env".to_string(), Value::Object(env_obj));
         }
     }
 
-    let pretty_json = serde_json::to_string_pretty(&json)
-        .map_err(|e| format!("Failed to serialize claude settings: {}", e))?;
+    serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("Failed to serialize claude settings: {}", e))
+}
+
+fn update_claude_settings(config: &EnvConfig) -> Result<(), String> {
+    let path = get_claude_settings_path()?;
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read claude settings: {}", e))?;
+
+    let pretty_json = render_claude_settings(&content, config)?;
 
-    fs::write(&path, pretty_json)
+    write_private(&path, pretty_json)
         .map_err(|e| format!("Failed to write claude settings: {}", e))?;
 
     Ok(())
 }
 
-#[tauri::command]
-fn read_env_config() -> Result<EnvConfig, String> {
-    let path = get_secrets_path();
+fn load_env_config() -> Result<EnvConfig, String> {
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
     Ok(EnvConfig {
         cs_base_url: parse_env_value(&content, "CS_BASE_URL"),
-        cs_auth_token: parse_env_value(&content, "CS_AUTH_TOKEN"),
+        cs_auth_token: parse_env_field(&content, "CS_AUTH_TOKEN").into(),
+        token_fingerprint: None,
     })
 }
 
 #[tauri::command]
-fn read_cs_config_groups() -> Result<Vec<CsConfigGroup>, String> {
-    let path = get_secrets_path();
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+fn read_env_config() -> Result<EnvConfig, String> {
+    // The token serializes masked; the fingerprint identifies it
+    let config = load_env_config()?;
+    let token_fingerprint = value_fingerprint(config.cs_auth_token.expose());
+    Ok(EnvConfig { token_fingerprint, ..config })
+}
+
+/// A BASE_URL/AUTH_TOKEN pair from the secrets file. Each group is two
+/// consecutive export lines; inactive groups are commented out with `#`.
+pub(crate) struct ParsedGroup {
+    pub base_url: String,
+    pub auth_token: Secret,
+    pub active: bool,
+}
 
-    let mut groups: Vec<CsConfigGroup> = Vec::new();
+pub(crate) fn parse_config_groups(content: &str, url_key: &str, token_key: &str) -> Vec<ParsedGroup> {
+    let mut groups: Vec<ParsedGroup> = Vec::new();
     let lines: Vec<&str> = content.lines().collect();
 
     let mut i = 0;
     while i < lines.len() {
         let line = lines[i].trim();
 
-        // Check for active config: export CS_BASE_URL=
-        if line.starts_with("export CS_BASE_URL=") {
-            let base_url = line
-                .strip_prefix("export CS_BASE_URL=")
-                .unwrap_or("")
-                .trim_matches('"')
-                .to_string();
-
-            // Look for the next line with CS_AUTH_TOKEN
-            if i + 1 < lines.len() {
-                let next_line = lines[i + 1].trim();
-                if next_line.starts_with("export CS_AUTH_TOKEN=") {
-                    let auth_token = next_line
-                        .strip_prefix("export CS_AUTH_TOKEN=")
-                        .unwrap_or("")
-                        .trim_matches('"')
-                        .to_string();
-
-                    groups.push(CsConfigGroup {
-                        base_url,
-                        auth_token,
-                        active: true,
+        // Active configs are exported, inactive ones are commented out
+        for (prefix, active) in [("export ", true), ("#export ", false)] {
+            let url_prefix = format!("{}{}=", prefix, url_key);
+            let token_prefix = format!("{}{}=", prefix, token_key);
+
+            if let Some(base_url) = line.strip_prefix(&url_prefix) {
+                // Look for the next line with the matching AUTH_TOKEN
+                if let Some(auth_token) = lines
+                    .get(i + 1)
+                    .and_then(|next| next.trim().strip_prefix(&token_prefix))
+                {
+                    // A group resolved from a reference is that reference
+                    let (token, reference) = split_export_value(auth_token);
+                    groups.push(ParsedGroup {
+                        base_url: base_url.trim_matches('"').to_string(),
+                        auth_token: Secret::new(reference.unwrap_or(token)),
+                        active,
                     });
-                    i += 2;
-                    continue;
-                }
-            }
-        }
-
-        // Check for commented config: #export CS_BASE_URL=
-        if line.starts_with("#export CS_BASE_URL=") {
-            let base_url = line
-                .strip_prefix("#export CS_BASE_URL=")
-                .unwrap_or("")
-                .trim_matches('"')
-                .to_string();
-
-            // Look for the next line with commented CS_AUTH_TOKEN
-            if i + 1 < lines.len() {
-                let next_line = lines[i + 1].trim();
-                if next_line.starts_with("#export CS_AUTH_TOKEN=") {
-                    let auth_token = next_line
-                        .strip_prefix("#export CS_AUTH_TOKEN=")
-                        .unwrap_or("")
-                        .trim_matches('"')
-                        .to_string();
-
-                    groups.push(CsConfigGroup {
-                        base_url,
-                        auth_token,
-                        active: false,
-                    });
-                    i += 2;
-                    continue;
+                    i += 1;
+                    break;
                 }
             }
         }
@@ -228,16 +239,38 @@ fn read_cs_config_groups() -> Result<Vec<CsConfigGroup>, String> {
         i += 1;
     }
 
-    Ok(groups)
+    groups
+}
+
+fn load_config_groups(url_key: &str, token_key: &str) -> Result<Vec<ParsedGroup>, String> {
+    let path = get_secrets_path()?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    Ok(parse_config_groups(&content, url_key, token_key))
+}
+
+#[tauri::command]
+fn read_cs_config_groups() -> Result<Vec<CsConfigGroup>, String> {
+    Ok(load_config_groups("CS_BASE_URL", "CS_AUTH_TOKEN")?
+        .into_iter()
+        .map(|g| CsConfigGroup {
+            token_fingerprint: value_fingerprint(g.auth_token.expose()),
+            base_url: g.base_url,
+            auth_token: g.auth_token,
+            active: g.active,
+        })
+        .collect())
 }
 
 #[tauri::command]
 fn switch_cs_config(index: usize) -> Result<(), String> {
-    let path = get_secrets_path();
+    require_switch_allowed()?;
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    let groups = read_cs_config_groups()?;
+    let groups = load_config_groups("CS_BASE_URL", "CS_AUTH_TOKEN")?;
     if index >= groups.len() {
         return Err("Invalid config index".to_string());
     }
@@ -271,23 +304,23 @@ fn switch_cs_config(index: usize) -> Result<(), String> {
         }
 
         if trimmed.starts_with("#export CS_AUTH_TOKEN=") {
-            let token = trimmed
-                .strip_prefix("#export CS_AUTH_TOKEN=")
-                .unwrap_or("")
-                .trim_matches('"');
-            if token == target_group.auth_token {
+            let (token, kept) = split_export_value(trimmed.strip_prefix("#export CS_AUTH_TOKEN=").unwrap_or(""));
+            if kept.unwrap_or(token) == target_group.auth_token.expose() {
                 *line = trimmed.strip_prefix("#").unwrap_or(&trimmed).to_string();
             }
         }
     }
 
-    fs::write(&path, lines.join("\n"))
+    let sealed = vault::seal_groups(&lines.join("\n"), "CS_AUTH_TOKEN")?;
+    write_private(&path, sealed)
         .map_err(|e| format!("Failed to write file: {}", e))?;
+    shell::bump_generation();
 
     // Also update ~/.claude/settings.json
     let config = EnvConfig {
         cs_base_url: target_group.base_url.clone(),
-        cs_auth_token: target_group.auth_token.clone(),
+        cs_auth_token: secret_refs::resolve_token(target_group.auth_token.expose())?.into(),
+        token_fingerprint: None,
     };
     update_claude_settings(&config)?;
 
@@ -296,88 +329,25 @@ fn switch_cs_config(index: usize) -> Result<(), String> {
 
 #[tauri::command]
 fn read_anthropic_config_groups() -> Result<Vec<AnthropicConfigGroup>, String> {
-    let path = get_secrets_path();
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let mut groups: Vec<AnthropicConfigGroup> = Vec::new();
-    let lines: Vec<&str> = content.lines().collect();
-
-    let mut i = 0;
-    while i < lines.len() {
-        let line = lines[i].trim();
-
-        // Check for active config: export ANTHROPIC_BASE_URL=
-        if line.starts_with("export ANTHROPIC_BASE_URL=") {
-            let base_url = line
-                .strip_prefix("export ANTHROPIC_BASE_URL=")
-                .unwrap_or("")
-                .trim_matches('"')
-                .to_string();
-
-            // Look for the next line with ANTHROPIC_AUTH_TOKEN
-            if i + 1 < lines.len() {
-                let next_line = lines[i + 1].trim();
-                if next_line.starts_with("export ANTHROPIC_AUTH_TOKEN=") {
-                    let auth_token = next_line
-                        .strip_prefix("export ANTHROPIC_AUTH_TOKEN=")
-                        .unwrap_or("")
-                        .trim_matches('"')
-                        .to_string();
-
-                    groups.push(AnthropicConfigGroup {
-                        base_url,
-                        auth_token,
-                        active: true,
-                    });
-                    i += 2;
-                    continue;
-                }
-            }
-        }
-
-        // Check for commented config: #export ANTHROPIC_BASE_URL=
-        if line.starts_with("#export ANTHROPIC_BASE_URL=") {
-            let base_url = line
-                .strip_prefix("#export ANTHROPIC_BASE_URL=")
-                .unwrap_or("")
-                .trim_matches('"')
-                .to_string();
-
-            // Look for the next line with commented ANTHROPIC_AUTH_TOKEN
-            if i + 1 < lines.len() {
-                let next_line = lines[i + 1].trim();
-                if next_line.starts_with("#export ANTHROPIC_AUTH_TOKEN=") {
-                    let auth_token = next_line
-                        .strip_prefix("#export ANTHROPIC_AUTH_TOKEN=")
-                        .unwrap_or("")
-                        .trim_matches('"')
-                        .to_string();
-
-                    groups.push(AnthropicConfigGroup {
-                        base_url,
-                        auth_token,
-                        active: false,
-                    });
-                    i += 2;
-                    continue;
-                }
-            }
-        }
-
-        i += 1;
-    }
-
-    Ok(groups)
+    Ok(load_config_groups("ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN")?
+        .into_iter()
+        .map(|g| AnthropicConfigGroup {
+            token_fingerprint: value_fingerprint(g.auth_token.expose()),
+            base_url: g.base_url,
+            auth_token: g.auth_token,
+            active: g.active,
+        })
+        .collect())
 }
 
 #[tauri::command]
 fn switch_anthropic_config(index: usize) -> Result<(), String> {
-    let path = get_secrets_path();
+    require_switch_allowed()?;
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    let groups = read_anthropic_config_groups()?;
+    let groups = load_config_groups("ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN")?;
     if index >= groups.len() {
         return Err("Invalid config index".to_string());
     }
@@ -411,77 +381,114 @@ fn switch_anthropic_config(index: usize) -> Result<(), String> {
         }
 
         if trimmed.starts_with("#export ANTHROPIC_AUTH_TOKEN=") {
-            let token = trimmed
-                .strip_prefix("#export ANTHROPIC_AUTH_TOKEN=")
-                .unwrap_or("")
-                .trim_matches('"');
-            if token == target_group.auth_token {
+            let (token, kept) = split_export_value(trimmed.strip_prefix("#export ANTHROPIC_AUTH_TOKEN=").unwrap_or(""));
+            if kept.unwrap_or(token) == target_group.auth_token.expose() {
                 *line = trimmed.strip_prefix("#").unwrap_or(&trimmed).to_string();
             }
         }
     }
 
-    fs::write(&path, lines.join("\n"))
+    let sealed = vault::seal_groups(&lines.join("\n"), "ANTHROPIC_AUTH_TOKEN")?;
+    write_private(&path, sealed)
         .map_err(|e| format!("Failed to write file: {}", e))?;
+    shell::bump_generation();
 
     Ok(())
 }
 
 #[tauri::command]
-fn save_env_config(config: EnvConfig) -> Result<(), String> {
+fn save_env_config(mut config: EnvConfig) -> Result<Vec<String>, String> {
+    require_app_unlocked()?;
+    let normalized = normalize_url(&config.cs_base_url, UrlStyle::Anthropic)?;
+    config.cs_base_url = normalized.url;
+
     // Update ~/.zshrc_secrets
-    let path = get_secrets_path();
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
+    // The form may hold any group's token, as picked from the group list
+    let existing: Vec<Secret> = parse_config_groups(&content, "CS_BASE_URL", "CS_AUTH_TOKEN")
+        .into_iter()
+        .map(|g| g.auth_token)
+        .collect();
+    let field = unmask(config.cs_auth_token.expose(), &existing)?;
+    let token = secret_refs::resolve_token(&field)?;
+    let mut warnings = normalized.warnings;
+    config.cs_auth_token = checked_token(&token, Protocol::Anthropic, &config.cs_base_url, &mut warnings)?.into();
+
+    let reference = is_reference(&field).then_some(field.as_str());
     let mut updated = update_env_value(&content, "CS_BASE_URL", &config.cs_base_url);
-    updated = update_env_value(&updated, "CS_AUTH_TOKEN", &config.cs_auth_token);
+    updated = update_env_export(&updated, "CS_AUTH_TOKEN", config.cs_auth_token.expose(), reference);
 
-    fs::write(&path, updated)
+    write_private(&path, updated)
         .map_err(|e| format!("Failed to write file: {}", e))?;
+    shell::bump_generation();
 
     // Update ~/.claude/settings.json
     update_claude_settings(&config)?;
 
-    Ok(())
+    Ok(warnings)
 }
 
-#[tauri::command]
-fn read_anthropic_config() -> Result<AnthropicConfig, String> {
-    let path = get_secrets_path();
+pub(crate) fn load_anthropic_config() -> Result<AnthropicConfig, String> {
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
     Ok(AnthropicConfig {
         base_url: parse_env_value(&content, "ANTHROPIC_BASE_URL"),
-        auth_token: parse_env_value(&content, "ANTHROPIC_AUTH_TOKEN"),
+        auth_token: parse_env_field(&content, "ANTHROPIC_AUTH_TOKEN").into(),
+        token_fingerprint: None,
     })
 }
 
 #[tauri::command]
-fn save_anthropic_config(config: AnthropicConfig) -> Result<(), String> {
-    let path = get_secrets_path();
+fn read_anthropic_config() -> Result<AnthropicConfig, String> {
+    let config = load_anthropic_config()?;
+    let token_fingerprint = value_fingerprint(config.auth_token.expose());
+    Ok(AnthropicConfig { token_fingerprint, ..config })
+}
+
+#[tauri::command]
+fn save_anthropic_config(mut config: AnthropicConfig) -> Result<Vec<String>, String> {
+    require_app_unlocked()?;
+    let normalized = normalize_url(&config.base_url, UrlStyle::Anthropic)?;
+    config.base_url = normalized.url;
+
+    let path = get_secrets_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
+    // The form may hold any group's token, as picked from the group list
+    let existing: Vec<Secret> = parse_config_groups(&content, "ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN")
+        .into_iter()
+        .map(|g| g.auth_token)
+        .collect();
+    let field = unmask(config.auth_token.expose(), &existing)?;
+    let token = secret_refs::resolve_token(&field)?;
+    let mut warnings = normalized.warnings;
+    config.auth_token = checked_token(&token, Protocol::Anthropic, &config.base_url, &mut warnings)?.into();
+
+    let reference = is_reference(&field).then_some(field.as_str());
     let mut updated = update_env_value(&content, "ANTHROPIC_BASE_URL", &config.base_url);
-    updated = update_env_value(&updated, "ANTHROPIC_AUTH_TOKEN", &config.auth_token);
+    updated = update_env_export(&updated, "ANTHROPIC_AUTH_TOKEN", config.auth_token.expose(), reference);
 
-    fs::write(&path, updated)
+    write_private(&path, updated)
         .map_err(|e| format!("Failed to write file: {}", e))?;
+    shell::bump_generation();
 
-    Ok(())
+    Ok(warnings)
 }
 
-#[tauri::command]
-fn read_codex_config() -> Result<CodexConfig, String> {
+pub(crate) fn load_codex_config() -> Result<CodexConfig, String> {
     // Read base_url from config.toml
-    let config_path = get_codex_config_path();
+    let config_path = get_codex_config_path()?;
     let config_content = fs::read_to_string(&config_path)
         .map_err(|e| format!("Failed to read codex config: {}", e))?;
 
     let toml: TomlValue = config_content.parse()
-        .map_err(|e| format!("Failed to parse codex config: {}", e))?;
+        .map_err(|e| format!("Failed to parse codex config: {}", redacted(e)))?;
 
     let base_url = toml
         .get("model_providers")
@@ -492,12 +499,12 @@ fn read_codex_config() -> Result<CodexConfig, String> {
         .to_string();
 
     // Read api_key from auth.json
-    let auth_path = get_codex_auth_path();
+    let auth_path = get_codex_auth_path()?;
     let auth_content = fs::read_to_string(&auth_path)
         .map_err(|e| format!("Failed to read codex auth: {}", e))?;
 
     let auth_json: Value = serde_json::from_str(&auth_content)
-        .map_err(|e| format!("Failed to parse codex auth: {}", e))?;
+        .map_err(|e| format!("Failed to parse codex auth: {}", redacted(e)))?;
 
     let api_key = auth_json
         .get("OPENAI_API_KEY")
@@ -505,18 +512,23 @@ fn read_codex_config() -> Result<CodexConfig, String> {
         .unwrap_or("")
         .to_string();
 
-    Ok(CodexConfig { base_url, api_key })
+    Ok(CodexConfig { base_url, api_key: api_key.into(), token_fingerprint: None })
+}
+
+/// A tool config as read commands send it, with the key masked.
+fn masked_codex(config: CodexConfig) -> CodexConfig {
+    let token_fingerprint = value_fingerprint(config.api_key.expose());
+    CodexConfig { token_fingerprint, ..config }
 }
 
 #[tauri::command]
-fn save_codex_config(config: CodexConfig) -> Result<(), String> {
-    // Update base_url in config.toml
-    let config_path = get_codex_config_path();
-    let config_content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read codex config: {}", e))?;
+fn read_codex_config() -> Result<CodexConfig, String> {
+    load_codex_config().map(masked_codex)
+}
 
+fn render_codex_config(content: &str, base_url: &str) -> String {
     // Use regex-like replacement for TOML base_url under [model_providers.custom]
-    let mut lines: Vec<String> = config_content.lines().map(|s| s.to_string()).collect();
+    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
     let mut in_custom_section = false;
 
     for line in lines.iter_mut() {
@@ -526,42 +538,66 @@ fn save_codex_config(config: CodexConfig) -> Result<(), String> {
         } else if trimmed.starts_with('[') && in_custom_section {
             in_custom_section = false;
         } else if in_custom_section && trimmed.starts_with("base_url") {
-            *line = format!("base_url = \"{}\"", config.base_url);
+            *line = format!("base_url = \"{}\"", base_url);
         }
     }
 
-    fs::write(&config_path, lines.join("\n"))
+    lines.join("\n")
+}
+
+fn render_codex_auth(content: &str, api_key: &str) -> Result<String, String> {
+    let mut auth_json: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse codex auth: {}", redacted(e)))?;
+
+    if let Some(obj) = auth_json.as_object_mut() {
+        obj.insert("OPENAI_API_KEY".to_string(), Value::String(api_key.to_string()));
+    }
+
+    serde_json::to_string_pretty(&auth_json)
+        .map_err(|e| format!("Failed to serialize codex auth: {}", e))
+}
+
+#[tauri::command]
+fn save_codex_config(mut config: CodexConfig) -> Result<Vec<String>, String> {
+    require_app_unlocked()?;
+    let normalized = normalize_url(&config.base_url, UrlStyle::Openai)?;
+    config.base_url = normalized.url;
+    let existing = load_codex_config().map(|c| c.api_key).unwrap_or_default();
+    let key = unmask(config.api_key.expose(), &[existing])?;
+    let key = secret_refs::resolve_token(&key)?;
+    let mut warnings = normalized.warnings;
+    config.api_key = checked_token(&key, Protocol::Openai, &config.base_url, &mut warnings)?.into();
+
+    // Update base_url in config.toml
+    let config_path = get_codex_config_path()?;
+    let config_content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read codex config: {}", e))?;
+
+    let rendered = render_codex_config(&config_content, &config.base_url);
+    fs::write(&config_path, &rendered)
         .map_err(|e| format!("Failed to write codex config: {}", e))?;
+    note_self_write(&config_path, rendered.as_bytes());
 
     // Update OPENAI_API_KEY in auth.json
-    let auth_path = get_codex_auth_path();
+    let auth_path = get_codex_auth_path()?;
     let auth_content = fs::read_to_string(&auth_path)
         .map_err(|e| format!("Failed to read codex auth: {}", e))?;
 
-    let mut auth_json: Value = serde_json::from_str(&auth_content)
-        .map_err(|e| format!("Failed to parse codex auth: {}", e))?;
+    let pretty_json = render_codex_auth(&auth_content, config.api_key.expose())?;
 
-    if let Some(obj) = auth_json.as_object_mut() {
-        obj.insert("OPENAI_API_KEY".to_string(), Value::String(config.api_key));
-    }
-
-    let pretty_json = serde_json::to_string_pretty(&auth_json)
-        .map_err(|e| format!("Failed to serialize codex auth: {}", e))?;
-
-    fs::write(&auth_path, pretty_json)
+    write_private(&auth_path, pretty_json)
         .map_err(|e| format!("Failed to write codex auth: {}", e))?;
 
-    Ok(())
+    Ok(warnings)
 }
 
-#[tauri::command]
-fn read_droid_config() -> Result<CodexConfig, String> {
-    let path = get_droid_settings_path();
+pub(crate) fn load_droid_config() -> Result<CodexConfig, String> {
+    let path = get_droid_settings_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read droid settings: {}", e))?;
 
     let json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse droid settings: {}", e))?;
+        .map_err(|e| format!("Failed to parse droid settings: {}", redacted(e)))?;
 
     let base_url = json
         .get("customModels")
@@ -581,17 +617,16 @@ fn read_droid_config() -> Result<CodexConfig, String> {
         .unwrap_or("")
         .to_string();
 
-    Ok(CodexConfig { base_url, api_key })
+    Ok(CodexConfig { base_url, api_key: api_key.into(), token_fingerprint: None })
 }
 
-#[tauri::command]
-fn read_opencode_config() -> Result<CodexConfig, String> {
-    let path = get_opencode_config_path();
+pub(crate) fn load_opencode_config() -> Result<CodexConfig, String> {
+    let path = get_opencode_config_path()?;
     let content = fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read opencode config: {}", e))?;
 
     let json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse opencode config: {}", e))?;
+        .map_err(|e| format!("Failed to parse opencode config: {}", redacted(e)))?;
 
     let base_url = json
         .get("provider")
@@ -611,74 +646,142 @@ fn read_opencode_config() -> Result<CodexConfig, String> {
         .unwrap_or("")
         .to_string();
 
-    Ok(CodexConfig { base_url, api_key })
+    Ok(CodexConfig { base_url, api_key: api_key.into(), token_fingerprint: None })
 }
 
 #[tauri::command]
-fn apply_codex_to_droid(config: CodexConfig) -> Result<(), String> {
-    let path = get_droid_settings_path();
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read droid settings: {}", e))?;
+fn read_droid_config() -> Result<CodexConfig, String> {
+    load_droid_config().map(masked_codex)
+}
 
-    let mut json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse droid settings: {}", e))?;
+#[tauri::command]
+fn read_opencode_config() -> Result<CodexConfig, String> {
+    load_opencode_config().map(masked_codex)
+}
+
+/// Droid's first custom model talks either to an Anthropic or to an
+/// OpenAI-compatible endpoint, depending on its `provider`.
+fn droid_url_style(json: &Value) -> UrlStyle {
+    match json
+        .get("customModels")
+        .and_then(|cm| cm.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|m| m.get("provider"))
+        .and_then(|v| v.as_str())
+    {
+        Some("anthropic") => UrlStyle::Anthropic,
+        _ => UrlStyle::Openai,
+    }
+}
+
+fn render_droid_settings(content: &str, config: &CodexConfig) -> Result<String, String> {
+    let mut json: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse droid settings: {}", redacted(e)))?;
 
     // Update customModels[0].baseUrl and customModels[0].apiKey
     if let Some(custom_models) = json.get_mut("customModels") {
         if let Some(models) = custom_models.as_array_mut() {
             if let Some(first_model) = models.get_mut(0) {
                 if let Some(obj) = first_model.as_object_mut() {
-                    obj.insert("baseUrl".to_string(), Value::String(config.base_url));
-                    obj.insert("apiKey".to_string(), Value::String(config.api_key));
+                    obj.insert("baseUrl".to_string(), Value::String(config.base_url.clone()));
+                    obj.insert("apiKey".to_string(), Value::String(config.api_key.expose().to_string()));
                 }
             }
         }
     }
 
-    let pretty_json = serde_json::to_string_pretty(&json)
-        .map_err(|e| format!("Failed to serialize droid settings: {}", e))?;
-
-    fs::write(&path, pretty_json)
-        .map_err(|e| format!("Failed to write droid settings: {}", e))?;
+    serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("Failed to serialize droid settings: {}", e))
+}
 
-    Ok(())
+/// Keys a masked key applied from the Codex form may stand for: the Codex
+/// key it was read as, or the target's own key.
+fn stored_keys(target: Result<CodexConfig, String>) -> Vec<Secret> {
+    [load_codex_config(), target]
+        .into_iter()
+        .filter_map(|c| c.ok().map(|c| c.api_key))
+        .collect()
 }
 
 #[tauri::command]
-fn apply_codex_to_opencode(config: CodexConfig) -> Result<(), String> {
-    let path = get_opencode_config_path();
+fn apply_codex_to_droid(mut config: CodexConfig) -> Result<Vec<String>, String> {
+    require_app_unlocked()?;
+    let path = get_droid_settings_path()?;
     let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read opencode config: {}", e))?;
+        .map_err(|e| format!("Failed to read droid settings: {}", e))?;
+
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse droid settings: {}", redacted(e)))?;
+    let style = droid_url_style(&json);
+    let normalized = normalize_url(&config.base_url, style)?;
+    config.base_url = normalized.url;
+    let key = unmask(config.api_key.expose(), &stored_keys(load_droid_config()))?;
+    let key = secret_refs::resolve_token(&key)?;
+    let protocol = if style == UrlStyle::Anthropic { Protocol::Anthropic } else { Protocol::Openai };
+    let mut warnings = normalized.warnings;
+    config.api_key = checked_token(&key, protocol, &config.base_url, &mut warnings)?.into();
+
+    let pretty_json = render_droid_settings(&content, &config)?;
+
+    write_private(&path, pretty_json)
+        .map_err(|e| format!("Failed to write droid settings: {}", e))?;
+
+    Ok(warnings)
+}
 
-    let mut json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse opencode config: {}", e))?;
+fn render_opencode_config(content: &str, config: &CodexConfig) -> Result<String, String> {
+    let mut json: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse opencode config: {}", redacted(e)))?;
 
     // Update provider.openai.options.baseURL and provider.openai.options.apiKey
     if let Some(provider) = json.get_mut("provider") {
         if let Some(openai) = provider.get_mut("openai") {
             if let Some(options) = openai.get_mut("options") {
                 if let Some(obj) = options.as_object_mut() {
-                    obj.insert("baseURL".to_string(), Value::String(config.base_url));
-                    obj.insert("apiKey".to_string(), Value::String(config.api_key));
+                    obj.insert("baseURL".to_string(), Value::String(config.base_url.clone()));
+                    obj.insert("apiKey".to_string(), Value::String(config.api_key.expose().to_string()));
                 }
             }
         }
     }
 
-    let pretty_json = serde_json::to_string_pretty(&json)
-        .map_err(|e| format!("Failed to serialize opencode config: {}", e))?;
+    serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("Failed to serialize opencode config: {}", e))
+}
+
+#[tauri::command]
+fn apply_codex_to_opencode(mut config: CodexConfig) -> Result<Vec<String>, String> {
+    require_app_unlocked()?;
+    let normalized = normalize_url(&config.base_url, UrlStyle::Openai)?;
+    config.base_url = normalized.url;
+    let key = unmask(config.api_key.expose(), &stored_keys(load_opencode_config()))?;
+    let key = secret_refs::resolve_token(&key)?;
+    let mut warnings = normalized.warnings;
+    config.api_key = checked_token(&key, Protocol::Openai, &config.base_url, &mut warnings)?.into();
+
+    let path = get_opencode_config_path()?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read opencode config: {}", e))?;
 
-    fs::write(&path, pretty_json)
+    let pretty_json = render_opencode_config(&content, &config)?;
+
+    write_private(&path, pretty_json)
         .map_err(|e| format!("Failed to write opencode config: {}", e))?;
 
-    Ok(())
+    Ok(warnings)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![read_env_config, save_env_config, read_codex_config, save_codex_config, read_cs_config_groups, switch_cs_config, read_anthropic_config_groups, switch_anthropic_config, read_anthropic_config, save_anthropic_config, read_droid_config, read_opencode_config, apply_codex_to_droid, apply_codex_to_opencode])
+        .setup(|app| {
+            drift::spawn_drift_monitor(app.handle().clone());
+            lock::spawn_idle_monitor(app.handle().clone());
+            watch::spawn_config_watcher(app.handle().clone());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![read_env_config, save_env_config, read_codex_config, save_codex_config, read_cs_config_groups, switch_cs_config, read_anthropic_config_groups, switch_anthropic_config, read_anthropic_config, save_anthropic_config, read_droid_config, read_opencode_config, apply_codex_to_droid, apply_codex_to_opencode, profiles::list_profiles, profiles::save_profile, profiles::reveal_token, profiles::delete_profile, profiles::apply_profile_everywhere, urls::normalize_base_url, paths::resolve_config_paths, settings::get_app_settings, settings::save_app_settings, detect::detect_tools, bootstrap::init_tool_config, shell::shell_integration_status, shell::install_shell_integration, shell::remove_shell_integration, shell::shell_hook_snippet, bindings::bind_directory, bindings::list_bindings, bindings::remove_binding, projects::apply_profile_to_project, projects::clear_project_profile, projects::list_project_overrides, effective::resolve_effective_config, drift::check_consistency, drift::reconcile_consistency, enforce::list_enforcements, enforce::enforce_profile, enforce::stop_enforcing, enforce::enforcement_log, vault::vault_status, vault::unlock_vault, vault::lock_vault, vault::change_vault_passphrase, vault::migrate_to_vault, secret_refs::check_secret_reference, secret_refs::clear_secret_cache, perms::audit_file_permissions, perms::fix_file_permissions, leaks::scan_for_leaks, rotate::rotate_token, validate::validate_token, lock::app_lock_status, lock::lock_app, lock::unlock_app, lock::record_activity, bundles::export_profiles, bundles::preview_import, bundles::import_profiles])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,155 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::secret_refs::clear_cache;
+use crate::settings::read_app_settings;
+use crate::vault::{lock_vault, unlock_vault, vault_exists};
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const LOCKED_EVENT: &str = "app-locked";
+/// Commands refused while the app is locked fail with this message; the
+/// frontend recognises the `Locked` prefix.
+pub(crate) const LOCKED_ERROR: &str = "Locked: unlock my-switch with the vault passphrase";
+
+/// Set at startup when there is a vault, so restarting the app does not get
+/// around the lock.
+static LOCKED: AtomicBool = AtomicBool::new(false);
+static LAST_ACTIVITY: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Serialize)]
+pub struct AppLockStatus {
+    pub locked: bool,
+    /// Locking needs a vault, since its passphrase is what unlocks the app.
+    pub available: bool,
+    /// None when auto-lock is off.
+    pub idle_timeout_secs: Option<u64>,
+    pub switch_while_locked: bool,
+}
+
+fn idle_timeout() -> Option<Duration> {
+    match read_app_settings().ok().and_then(|s| s.auto_lock_minutes) {
+        Some(0) => None,
+        Some(minutes) => Some(Duration::from_secs(minutes * 60)),
+        None => Some(DEFAULT_IDLE_TIMEOUT),
+    }
+}
+
+fn touch() {
+    if let Ok(mut last) = LAST_ACTIVITY.lock() {
+        *last = Some(Instant::now());
+    }
+}
+
+fn idle_for() -> Duration {
+    LAST_ACTIVITY
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .map(|last| last.elapsed())
+        .unwrap_or_default()
+}
+
+pub(crate) fn is_app_locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+/// For commands that show, export or change tokens. Counts as activity.
+pub(crate) fn require_app_unlocked() -> Result<(), String> {
+    if is_app_locked() {
+        return Err(LOCKED_ERROR.to_string());
+    }
+    touch();
+    Ok(())
+}
+
+/// For commands that switch between existing profiles, which the settings
+/// may allow while the app is locked.
+pub(crate) fn require_switch_allowed() -> Result<(), String> {
+    if !is_app_locked() {
+        touch();
+        return Ok(());
+    }
+    let allowed = read_app_settings().map(|s| s.switch_while_locked).unwrap_or(false);
+    if allowed {
+        Ok(())
+    } else {
+        Err(LOCKED_ERROR.to_string())
+    }
+}
+
+fn lock(app: &AppHandle) -> Result<AppLockStatus, String> {
+    if !vault_exists() {
+        return Err("Create a vault first; its passphrase unlocks the app".to_string());
+    }
+
+    LOCKED.store(true, Ordering::SeqCst);
+    clear_cache();
+    // Switching to a profile kept in the vault needs the vault open
+    if !read_app_settings().map(|s| s.switch_while_locked).unwrap_or(false) {
+        lock_vault()?;
+    }
+    let _ = app.emit(LOCKED_EVENT, true);
+    app_lock_status()
+}
+
+#[tauri::command]
+pub fn app_lock_status() -> Result<AppLockStatus, String> {
+    let settings = read_app_settings()?;
+    Ok(AppLockStatus {
+        locked: is_app_locked(),
+        available: vault_exists(),
+        idle_timeout_secs: idle_timeout().map(|t| t.as_secs()),
+        switch_while_locked: settings.switch_while_locked,
+    })
+}
+
+#[tauri::command]
+pub fn lock_app(app: AppHandle) -> Result<AppLockStatus, String> {
+    lock(&app)
+}
+
+/// Unlocks the app and opens the vault with its passphrase.
+#[tauri::command]
+pub fn unlock_app(app: AppHandle, passphrase: String) -> Result<AppLockStatus, String> {
+    // Without a vault there is no passphrase to check; a new one must not be created here
+    if !vault_exists() {
+        return Err("There is no vault to unlock the app with".to_string());
+    }
+    unlock_vault(passphrase)?;
+
+    LOCKED.store(false, Ordering::SeqCst);
+    touch();
+    let _ = app.emit(LOCKED_EVENT, false);
+    app_lock_status()
+}
+
+/// Reported by the frontend on user input, so reading the app is not idling.
+#[tauri::command]
+pub fn record_activity() {
+    if !is_app_locked() {
+        touch();
+    }
+}
+
+/// Starts the app locked when there is a vault, and locks it once it has
+/// been idle for the configured period.
+pub(crate) fn spawn_idle_monitor(app: AppHandle) {
+    LOCKED.store(vault_exists(), Ordering::SeqCst);
+    touch();
+    thread::spawn(move || loop {
+        thread::sleep(IDLE_CHECK_INTERVAL);
+
+        // Re-read the timeout each round so settings changes apply without a restart
+        let Some(timeout) = idle_timeout() else {
+            continue;
+        };
+        if !is_app_locked() && vault_exists() && idle_for() >= timeout {
+            let _ = lock(&app);
+        }
+    });
+}
@@ -0,0 +1,187 @@
+use std::env;
+use std::path::PathBuf;
+use serde::Serialize;
+
+use crate::settings::{read_app_settings, PathOverrides};
+
+#[derive(Serialize)]
+pub struct ConfigPaths {
+    pub secrets: String,
+    pub claude_settings: String,
+    pub codex_config: String,
+    pub codex_auth: String,
+    pub droid_settings: String,
+    pub opencode_config: String,
+}
+
+fn home_dir() -> Result<PathBuf, String> {
+    dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())
+}
+
+/// Expands a leading `~` so overrides can be written the way users type them.
+pub(crate) fn expand_home(path: &str) -> Result<PathBuf, String> {
+    if path == "~" {
+        return home_dir();
+    }
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(home_dir()?.join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+fn env_dir(key: &str) -> Option<PathBuf> {
+    env::var_os(key)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+fn overrides() -> PathOverrides {
+    // A broken settings file should not make every tool unreachable
+    read_app_settings().map(|s| s.paths).unwrap_or_default()
+}
+
+/// Resolves a tool directory from, in order: the app settings override,
+/// the environment variable the tool itself honors, and the default.
+fn resolve_dir(
+    override_path: Option<String>,
+    env_key: Option<&str>,
+    default: impl FnOnce() -> Result<PathBuf, String>,
+) -> Result<PathBuf, String> {
+    if let Some(path) = override_path.filter(|p| !p.trim().is_empty()) {
+        return expand_home(path.trim());
+    }
+    if let Some(dir) = env_key.and_then(env_dir) {
+        return Ok(dir);
+    }
+    default()
+}
+
+pub(crate) fn get_secrets_path() -> Result<PathBuf, String> {
+    match overrides().secrets_file.filter(|p| !p.trim().is_empty()) {
+        Some(path) => expand_home(path.trim()),
+        None => Ok(home_dir()?.join(".zshrc_secrets")),
+    }
+}
+
+fn get_claude_config_dir() -> Result<PathBuf, String> {
+    resolve_dir(overrides().claude_config_dir, Some("CLAUDE_CONFIG_DIR"), || {
+        Ok(home_dir()?.join(".claude"))
+    })
+}
+
+pub(crate) fn get_claude_settings_path() -> Result<PathBuf, String> {
+    Ok(get_claude_config_dir()?.join("settings.json"))
+}
+
+/// Claude Code's state file, which lists every project it has been opened in.
+/// It lives next to the config dir when CLAUDE_CONFIG_DIR is set, in $HOME otherwise.
+pub(crate) fn get_claude_state_path() -> Result<PathBuf, String> {
+    let in_config_dir = get_claude_config_dir()?.join(".claude.json");
+    if in_config_dir.exists() {
+        return Ok(in_config_dir);
+    }
+    Ok(home_dir()?.join(".claude.json"))
+}
+
+/// Policy file administrators use to pin Claude Code settings. It overrides
+/// every other settings file.
+pub(crate) fn get_claude_managed_settings_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-settings.json")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\ClaudeCode\managed-settings.json")
+    } else {
+        PathBuf::from("/etc/claude-code/managed-settings.json")
+    }
+}
+
+fn get_codex_home() -> Result<PathBuf, String> {
+    resolve_dir(overrides().codex_home, Some("CODEX_HOME"), || {
+        Ok(home_dir()?.join(".codex"))
+    })
+}
+
+pub(crate) fn get_codex_config_path() -> Result<PathBuf, String> {
+    Ok(get_codex_home()?.join("config.toml"))
+}
+
+pub(crate) fn get_codex_auth_path() -> Result<PathBuf, String> {
+    Ok(get_codex_home()?.join("auth.json"))
+}
+
+/// Administrator-pinned Codex settings, layered over the user's config.toml.
+pub(crate) fn get_codex_managed_config_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        None
+    } else {
+        Some(PathBuf::from("/etc/codex/managed_config.toml"))
+    }
+}
+
+pub(crate) fn get_droid_settings_path() -> Result<PathBuf, String> {
+    let dir = resolve_dir(overrides().droid_dir, None, || {
+        Ok(home_dir()?.join(".factory"))
+    })?;
+    Ok(dir.join("settings.json"))
+}
+
+pub(crate) fn get_opencode_config_path() -> Result<PathBuf, String> {
+    // OpenCode uses XDG paths on every platform, not the OS config dir
+    let dir = resolve_dir(overrides().opencode_config_dir, None, || {
+        match env_dir("XDG_CONFIG_HOME") {
+            Some(dir) => Ok(dir.join("opencode")),
+            None => Ok(home_dir()?.join(".config").join("opencode")),
+        }
+    })?;
+    Ok(dir.join("opencode.json"))
+}
+
+fn get_app_config_dir() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| dir.join("my-switch"))
+        .ok_or_else(|| "Could not find config directory".to_string())
+}
+
+pub(crate) fn get_app_settings_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("settings.json"))
+}
+
+pub(crate) fn get_profiles_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("profiles.json"))
+}
+
+pub(crate) fn get_bindings_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("bindings.json"))
+}
+
+/// Tokens encrypted under the user's passphrase.
+pub(crate) fn get_vault_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("vault.json"))
+}
+
+/// Which profile each tool is held to in enforce mode.
+pub(crate) fn get_enforcements_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("enforce.json"))
+}
+
+/// One JSON line per correction made in enforce mode.
+pub(crate) fn get_enforce_log_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("enforce.log"))
+}
+
+/// Bumped on every switch so shell hooks know when to re-source the secrets.
+pub(crate) fn get_generation_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join("generation"))
+}
+
+#[tauri::command]
+pub fn resolve_config_paths() -> Result<ConfigPaths, String> {
+    Ok(ConfigPaths {
+        secrets: get_secrets_path()?.display().to_string(),
+        claude_settings: get_claude_settings_path()?.display().to_string(),
+        codex_config: get_codex_config_path()?.display().to_string(),
+        codex_auth: get_codex_auth_path()?.display().to_string(),
+        droid_settings: get_droid_settings_path()?.display().to_string(),
+        opencode_config: get_opencode_config_path()?.display().to_string(),
+    })
+}
@@ -0,0 +1,159 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use crate::paths::{
+    get_claude_settings_path, get_codex_auth_path, get_droid_settings_path, get_opencode_config_path,
+    get_profiles_path, get_secrets_path, get_vault_path,
+};
+use crate::projects::project_credential_files;
+use crate::watch::note_self_write;
+
+/// Owner read and write, nothing for group and others.
+#[cfg(unix)]
+pub(crate) const PRIVATE_MODE: u32 = 0o600;
+#[cfg(unix)]
+const GROUP_OTHER_BITS: u32 = 0o077;
+
+#[derive(Serialize)]
+pub struct FilePermissions {
+    pub path: String,
+    /// The mode in octal, e.g. `0644`. None on platforms without Unix modes.
+    pub mode: Option<String>,
+    pub owner_uid: Option<u32>,
+    /// Why the file is unsafe; empty when it is fine.
+    pub problems: Vec<String>,
+}
+
+/// Writes a file that holds credentials. New files are created 0600; an
+/// existing file keeps its mode, minus any group and other bits, which are
+/// removed before the new content goes in.
+pub(crate) fn write_private(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(PRIVATE_MODE);
+    }
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = file.metadata()?.permissions().mode();
+        if mode & GROUP_OTHER_BITS != 0 {
+            file.set_permissions(fs::Permissions::from_mode(mode & !GROUP_OTHER_BITS))?;
+        }
+    }
+    file.write_all(contents)?;
+    note_self_write(path, contents);
+    Ok(())
+}
+
+/// Every file that may hold a token: the tool configs that take one, the
+/// app's own profile store and vault, and project settings with a token.
+fn credential_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = [
+        get_secrets_path(),
+        get_claude_settings_path(),
+        get_codex_auth_path(),
+        get_droid_settings_path(),
+        get_opencode_config_path(),
+        get_profiles_path(),
+        get_vault_path(),
+    ]
+    .into_iter()
+    .filter_map(Result::ok)
+    .chain(project_credential_files())
+    .filter(|path| path.exists())
+    .collect();
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[cfg(unix)]
+fn inspect(path: &Path) -> Result<FilePermissions, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mode = metadata.mode() & 0o777;
+
+    let mut problems = Vec::new();
+    if mode & 0o070 != 0 {
+        problems.push("accessible to its group".to_string());
+    }
+    if mode & 0o007 != 0 {
+        problems.push("accessible to other users".to_string());
+    }
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    if metadata.uid() != uid {
+        problems.push(format!("owned by uid {}, not the current user", metadata.uid()));
+    }
+
+    Ok(FilePermissions {
+        path: path.display().to_string(),
+        mode: Some(format!("{:04o}", mode)),
+        owner_uid: Some(metadata.uid()),
+        problems,
+    })
+}
+
+#[cfg(not(unix))]
+fn inspect(path: &Path) -> Result<FilePermissions, String> {
+    Ok(FilePermissions {
+        path: path.display().to_string(),
+        mode: None,
+        owner_uid: None,
+        problems: Vec::new(),
+    })
+}
+
+/// Sets a file that others can access to 0600. Private files are left
+/// alone, so a read-only 0400 is not loosened.
+#[cfg(unix)]
+fn make_private(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if metadata.permissions().mode() & GROUP_OTHER_BITS == 0 {
+        return Ok(());
+    }
+    fs::set_permissions(path, fs::Permissions::from_mode(PRIVATE_MODE))
+        .map_err(|e| format!("Failed to change mode of {}: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn make_private(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+/// Reports the mode and owner of every credential file that exists.
+#[tauri::command]
+pub fn audit_file_permissions() -> Result<Vec<FilePermissions>, String> {
+    credential_files().iter().map(|path| inspect(path)).collect()
+}
+
+/// Sets unsafe credential files to 0600 and returns the audit afterwards.
+/// `paths` limits the repair to those files; ownership is only reported,
+/// since changing it needs root.
+#[tauri::command]
+pub fn fix_file_permissions(paths: Option<Vec<String>>) -> Result<Vec<FilePermissions>, String> {
+    for path in credential_files() {
+        if let Some(paths) = &paths {
+            if !paths.iter().any(|p| Path::new(p) == path) {
+                continue;
+            }
+        }
+        make_private(&path)?;
+    }
+
+    audit_file_permissions()
+}
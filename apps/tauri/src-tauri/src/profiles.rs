@@ -0,0 +1,661 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::edits::{EditPlan, FileDiff};
+use crate::enforce::retarget;
+use crate::lock::{is_app_locked, require_app_unlocked, require_switch_allowed, LOCKED_ERROR};
+use crate::perms::write_private;
+use crate::shell::bump_generation;
+use crate::tools::Tool;
+use crate::urls::{normalize_url, UrlStyle};
+use crate::validate::checked_token;
+use crate::secret::Secret;
+use crate::redact::{is_masked, redacted, unmask, value_fingerprint};
+use crate::secret_refs::{is_reference, resolve_token};
+use crate::vault::{forget_token, lookup_token, profile_entry, require_unlocked, store_tokens, vault_exists};
+use crate::paths::{
+    get_claude_settings_path, get_codex_auth_path, get_codex_config_path, get_droid_settings_path,
+    get_opencode_config_path, get_profiles_path, get_secrets_path,
+};
+use crate::{
+    droid_url_style, load_anthropic_config, load_codex_config, load_droid_config, load_opencode_config,
+    parse_config_groups, render_codex_auth, render_codex_config, render_droid_settings, render_opencode_config,
+    update_env_value, CodexConfig,
+};
+
+/// Which API families a provider endpoint speaks.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Anthropic,
+    Openai,
+    /// A gateway that serves both the Anthropic and the OpenAI-compatible API.
+    Both,
+}
+
+impl Protocol {
+    pub(crate) fn serves(self, wanted: Protocol) -> bool {
+        self == Protocol::Both || self == wanted
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProviderProfile {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    /// Serializes masked; only `write_profiles` writes it out in full.
+    pub api_key: Secret,
+    pub protocol: Protocol,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Identifies the key, which `list_profiles` sends masked.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub key_fingerprint: Option<String>,
+}
+
+impl ProviderProfile {
+    /// The profile with its key in plaintext, for writing into tool configs.
+    /// The key may be a reference to the vault, a password manager, a file,
+    /// a command or an environment variable, or the masked form of the key
+    /// saved under this profile's id.
+    pub(crate) fn resolved(mut self) -> Result<ProviderProfile, String> {
+        if is_masked(self.api_key.expose()) {
+            self.api_key = unmask(self.api_key.expose(), &stored_key(&self.id)?)?.into();
+        }
+        self.api_key = resolve_token(self.api_key.expose())?.into();
+        Ok(self)
+    }
+
+    /// The profile a switch may write. While the app is locked that is the
+    /// saved profile with this id, so a switch cannot bring in a new key,
+    /// endpoint or reference.
+    pub(crate) fn switchable(self) -> Result<ProviderProfile, String> {
+        require_switch_allowed()?;
+        if !is_app_locked() {
+            return Ok(self);
+        }
+        read_profiles()?
+            .into_iter()
+            .find(|p| !self.id.is_empty() && p.id == self.id)
+            .ok_or_else(|| LOCKED_ERROR.to_string())
+    }
+
+    fn masked(self) -> ProviderProfile {
+        ProviderProfile {
+            key_fingerprint: value_fingerprint(self.api_key.expose()),
+            ..self
+        }
+    }
+}
+
+/// The key saved for profile `id`, if there is one, for unmasking.
+fn stored_key(id: &str) -> Result<Vec<Secret>, String> {
+    Ok(read_profiles()?
+        .into_iter()
+        .filter(|p| !id.is_empty() && p.id == id)
+        .map(|p| p.api_key)
+        .collect())
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyStatus {
+    Applied,
+    Unchanged,
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize)]
+pub struct ToolApplyResult {
+    pub tool: Tool,
+    pub status: ApplyStatus,
+    pub message: Option<String>,
+    pub warnings: Vec<String>,
+    pub diffs: Vec<FileDiff>,
+}
+
+#[derive(Serialize)]
+pub struct ApplyReport {
+    /// False when nothing was written, either because of `dry_run` or
+    /// because one of the tools failed and the whole apply was abandoned.
+    pub committed: bool,
+    pub results: Vec<ToolApplyResult>,
+}
+
+enum Staged {
+    Files { paths: Vec<PathBuf>, warnings: Vec<String> },
+    Skipped(String),
+}
+
+pub(crate) fn read_profiles() -> Result<Vec<ProviderProfile>, String> {
+    let path = get_profiles_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read profiles: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse profiles: {}", redacted(e)))
+}
+
+/// A profile as written to the profile store, with the key in plaintext.
+struct StoredProfile<'a> {
+    profile: &'a ProviderProfile,
+}
+
+impl Serialize for StoredProfile<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let p = self.profile;
+        let mut state = serializer.serialize_struct("ProviderProfile", 6)?;
+        state.serialize_field("id", &p.id)?;
+        state.serialize_field("name", &p.name)?;
+        state.serialize_field("base_url", &p.base_url)?;
+        state.serialize_field("api_key", p.api_key.expose())?;
+        state.serialize_field("protocol", &p.protocol)?;
+        state.serialize_field("model", &p.model)?;
+        state.end()
+    }
+}
+
+pub(crate) fn write_profiles(profiles: &[ProviderProfile]) -> Result<(), String> {
+    let path = get_profiles_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create profiles directory: {}", e))?;
+    }
+
+    // Profiles serialize with their key masked; only the store holds it in full
+    let stored: Vec<StoredProfile> = profiles.iter().map(|profile| StoredProfile { profile }).collect();
+    let pretty_json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+    write_private(&path, pretty_json)
+        .map_err(|e| format!("Failed to write profiles: {}", e))
+}
+
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    slug.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+fn group_profile(family: &str, index: usize, base_url: String, api_key: Secret) -> ProviderProfile {
+    ProviderProfile {
+        id: format!("{}:{}", family, index),
+        name: format!("{} group {}", family, index),
+        base_url,
+        api_key,
+        protocol: Protocol::Anthropic,
+        model: None,
+        key_fingerprint: None,
+    }
+}
+
+/// Looks up a profile by selector: `anthropic` or `cs` for the active group
+/// in the secrets file, `anthropic:N` or `cs:N` for the group at index N, and
+/// otherwise a saved profile id or name.
+pub(crate) fn resolve_profile(selector: &str) -> Result<ProviderProfile, String> {
+    let (family, index) = match selector.split_once(':') {
+        Some((family, index)) => (family, Some(index)),
+        None => (selector, None),
+    };
+
+    if family == "anthropic" || family == "cs" {
+        let (url_key, token_key) = if family == "anthropic" {
+            ("ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN")
+        } else {
+            ("CS_BASE_URL", "CS_AUTH_TOKEN")
+        };
+        let content = fs::read_to_string(get_secrets_path()?)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let groups = parse_config_groups(&content, url_key, token_key);
+
+        let position = match index {
+            Some(index) => index
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid group index: {}", index))?,
+            None => groups
+                .iter()
+                .position(|g| g.active)
+                .ok_or_else(|| format!("No active {} group", family))?,
+        };
+
+        let group = groups
+            .into_iter()
+            .nth(position)
+            .ok_or_else(|| format!("No {} group at index {}", family, position))?;
+
+        return group_profile(family, position, group.base_url, group.auth_token).resolved();
+    }
+
+    let profiles = read_profiles()?;
+    profiles
+        .iter()
+        .find(|p| p.id == selector)
+        .or_else(|| profiles.iter().find(|p| p.name.eq_ignore_ascii_case(selector)))
+        .cloned()
+        .ok_or_else(|| format!("Profile not found: {}", selector))?
+        .resolved()
+}
+
+#[tauri::command]
+pub fn list_profiles() -> Result<Vec<ProviderProfile>, String> {
+    Ok(read_profiles()?.into_iter().map(ProviderProfile::masked).collect())
+}
+
+/// Checks `incoming` and merges it into a copy of `profiles`, giving new
+/// profiles an id. Returns the merged list and the ids of the incoming
+/// profiles, in order. Nothing is written.
+pub(crate) fn merged_profiles(
+    profiles: &[ProviderProfile],
+    incoming: Vec<ProviderProfile>,
+) -> Result<(Vec<ProviderProfile>, Vec<String>), String> {
+    let mut merged = profiles.to_vec();
+    let mut ids = Vec::new();
+
+    for mut profile in incoming {
+        if profile.name.trim().is_empty() {
+            return Err("Profile name is required".to_string());
+        }
+        if profile.id.is_empty() {
+            let base = slugify(&profile.name);
+            let base = if base.is_empty() { "profile".to_string() } else { base };
+            let mut id = base.clone();
+            let mut n = 2;
+            while merged.iter().any(|p| p.id == id) {
+                id = format!("{}-{}", base, n);
+                n += 1;
+            }
+            profile.id = id;
+        }
+        if is_masked(profile.api_key.expose()) {
+            profile.api_key = unmask(profile.api_key.expose(), &stored_key(&profile.id)?)?.into();
+        }
+        // A profile may be saved before its key is known
+        if !profile.api_key.is_empty() {
+            profile.api_key =
+                checked_token(profile.api_key.expose(), profile.protocol, &profile.base_url, &mut Vec::new())?.into();
+        }
+
+        ids.push(profile.id.clone());
+        match merged.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile,
+            None => merged.push(profile),
+        }
+    }
+    Ok((merged, ids))
+}
+
+/// Saves several profiles at once, all or nothing: every profile is checked
+/// before anything is written, and the profile store is written once.
+/// Returns the saved profiles, in order.
+pub(crate) fn save_profiles(incoming: Vec<ProviderProfile>) -> Result<Vec<ProviderProfile>, String> {
+    let (mut profiles, ids) = merged_profiles(&read_profiles()?, incoming)?;
+
+    // Once there is a vault, the profile store only holds references to it
+    let mut previous: Vec<(String, Option<Secret>)> = Vec::new();
+    if vault_exists() {
+        let pending: Vec<usize> = (0..profiles.len())
+            .filter(|&i| {
+                let profile = &profiles[i];
+                ids.contains(&profile.id) && !profile.api_key.is_empty() && !is_reference(profile.api_key.expose())
+            })
+            .collect();
+        let entries: Vec<(String, String)> = pending
+            .iter()
+            .map(|&i| (profile_entry(&profiles[i].id), profiles[i].api_key.expose().to_string()))
+            .collect();
+        previous = entries.iter().map(|(id, _)| (id.clone(), lookup_token(id).ok().map(Secret::new))).collect();
+
+        for (i, reference) in pending.into_iter().zip(store_tokens(entries)?) {
+            profiles[i].api_key = reference.into();
+        }
+    }
+
+    if let Err(e) = write_profiles(&profiles) {
+        // Put back what the vault held, so a failed save changes nothing
+        for (id, token) in previous {
+            match token {
+                Some(token) => {
+                    let _ = store_tokens(vec![(id, token.expose().to_string())]);
+                }
+                None => forget_token(&id),
+            }
+        }
+        return Err(e);
+    }
+
+    Ok(ids
+        .iter()
+        .filter_map(|id| profiles.iter().find(|p| p.id == *id).cloned())
+        .collect())
+}
+
+#[tauri::command]
+pub fn save_profile(profile: ProviderProfile) -> Result<ProviderProfile, String> {
+    require_app_unlocked()?;
+    let saved = save_profiles(vec![profile])?;
+    saved
+        .into_iter()
+        .next()
+        .map(ProviderProfile::masked)
+        .ok_or_else(|| "Failed to save profile".to_string())
+}
+
+/// The plaintext key of a profile, for showing it on request. Accepts the
+/// selectors `resolve_profile` does, and `tool:<name>` for the key a tool's
+/// config holds. Needs the vault unlocked when there is one.
+#[tauri::command]
+pub fn reveal_token(profile_id: String) -> Result<String, String> {
+    require_app_unlocked()?;
+    require_unlocked()?;
+
+    let key = match profile_id.strip_prefix("tool:") {
+        Some("claude") => load_anthropic_config()?.auth_token,
+        Some("codex") => load_codex_config()?.api_key,
+        Some("droid") => load_droid_config()?.api_key,
+        Some("opencode") => load_opencode_config()?.api_key,
+        Some(tool) => return Err(format!("Unknown tool: {}", tool)),
+        None => resolve_profile(&profile_id)?.api_key,
+    };
+    resolve_token(key.expose())
+}
+
+#[tauri::command]
+pub fn delete_profile(id: String) -> Result<(), String> {
+    require_app_unlocked()?;
+    let mut profiles = read_profiles()?;
+    let before = profiles.len();
+    profiles.retain(|p| p.id != id);
+
+    if profiles.len() == before {
+        return Err(format!("Profile not found: {}", id));
+    }
+    forget_token(&profile_entry(&id));
+
+    write_profiles(&profiles)
+}
+
+fn missing(path: &std::path::Path) -> Option<Staged> {
+    if path.exists() {
+        None
+    } else {
+        Some(Staged::Skipped(format!("{} not found", path.display())))
+    }
+}
+
+fn set_json_string(json: &mut Value, key: &str, value: &str) {
+    if let Some(obj) = json.as_object_mut() {
+        obj.insert(key.to_string(), Value::String(value.to_string()));
+    }
+}
+
+/// Sets the top-level `model` key in a Codex config.toml, keeping the rest of
+/// the file as written.
+pub(crate) fn render_codex_model(content: &str, model: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+    let first_section = lines
+        .iter()
+        .position(|l| l.trim().starts_with('['))
+        .unwrap_or(lines.len());
+
+    let existing = lines[..first_section].iter().position(|l| {
+        let trimmed = l.trim();
+        trimmed.starts_with("model") && trimmed["model".len()..].trim_start().starts_with('=')
+    });
+
+    let line = format!("model = \"{}\"", model);
+    match existing {
+        Some(i) => lines[i] = line,
+        None => lines.insert(0, line),
+    }
+
+    lines.join("\n")
+}
+
+fn stage_claude(plan: &mut EditPlan, profile: &ProviderProfile) -> Result<Staged, String> {
+    if !profile.protocol.serves(Protocol::Anthropic) {
+        return Ok(Staged::Skipped(
+            "Profile does not expose an Anthropic-compatible endpoint".to_string(),
+        ));
+    }
+
+    let secrets_path = get_secrets_path()?;
+    if let Some(skipped) = missing(&secrets_path) {
+        return Ok(skipped);
+    }
+
+    let normalized = normalize_url(&profile.base_url, UrlStyle::Anthropic)?;
+
+    let content = plan.read(&secrets_path)?;
+    let mut updated = update_env_value(&content, "ANTHROPIC_BASE_URL", &normalized.url);
+    updated = update_env_value(&updated, "ANTHROPIC_AUTH_TOKEN", profile.api_key.expose());
+    plan.write(&secrets_path, updated);
+
+    let mut paths = vec![secrets_path];
+
+    if let Some(model) = &profile.model {
+        let settings_path = get_claude_settings_path()?;
+        if settings_path.exists() {
+            let mut json: Value = serde_json::from_str(&plan.read(&settings_path)?)
+                .map_err(|e| format!("Failed to parse claude settings: {}", redacted(e)))?;
+            set_json_string(&mut json, "model", model);
+
+            let pretty_json = serde_json::to_string_pretty(&json)
+                .map_err(|e| format!("Failed to serialize claude settings: {}", e))?;
+            plan.write(&settings_path, pretty_json);
+            paths.push(settings_path);
+        }
+    }
+
+    Ok(Staged::Files { paths, warnings: normalized.warnings })
+}
+
+fn stage_codex(plan: &mut EditPlan, profile: &ProviderProfile) -> Result<Staged, String> {
+    if !profile.protocol.serves(Protocol::Openai) {
+        return Ok(Staged::Skipped(
+            "Profile does not expose an OpenAI-compatible endpoint".to_string(),
+        ));
+    }
+
+    let config_path = get_codex_config_path()?;
+    let auth_path = get_codex_auth_path()?;
+    if let Some(skipped) = missing(&config_path).or_else(|| missing(&auth_path)) {
+        return Ok(skipped);
+    }
+
+    let normalized = normalize_url(&profile.base_url, UrlStyle::Openai)?;
+
+    let mut config_content = render_codex_config(&plan.read(&config_path)?, &normalized.url);
+    if let Some(model) = &profile.model {
+        config_content = render_codex_model(&config_content, model);
+    }
+    plan.write(&config_path, config_content);
+
+    let auth_content = render_codex_auth(&plan.read(&auth_path)?, profile.api_key.expose())?;
+    plan.write(&auth_path, auth_content);
+
+    Ok(Staged::Files {
+        paths: vec![config_path, auth_path],
+        warnings: normalized.warnings,
+    })
+}
+
+fn stage_droid(plan: &mut EditPlan, profile: &ProviderProfile) -> Result<Staged, String> {
+    let path = get_droid_settings_path()?;
+    if let Some(skipped) = missing(&path) {
+        return Ok(skipped);
+    }
+
+    let content = plan.read(&path)?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse droid settings: {}", redacted(e)))?;
+
+    // Droid talks to whichever API the first custom model is configured for
+    let style = droid_url_style(&json);
+    let wanted = match style {
+        UrlStyle::Anthropic => Protocol::Anthropic,
+        UrlStyle::Openai => Protocol::Openai,
+    };
+
+    if !profile.protocol.serves(wanted) {
+        return Ok(Staged::Skipped(format!(
+            "Droid's custom model uses the {:?} API, which this profile does not expose",
+            wanted
+        )));
+    }
+
+    let normalized = normalize_url(&profile.base_url, style)?;
+    let config = CodexConfig {
+        base_url: normalized.url,
+        api_key: profile.api_key.clone(),
+        token_fingerprint: None,
+    };
+    let mut updated = render_droid_settings(&content, &config)?;
+
+    if let Some(model) = &profile.model {
+        let mut json: Value = serde_json::from_str(&updated)
+            .map_err(|e| format!("Failed to parse droid settings: {}", redacted(e)))?;
+        if let Some(first_model) = json
+            .get_mut("customModels")
+            .and_then(|cm| cm.as_array_mut())
+            .and_then(|arr| arr.first_mut())
+        {
+            set_json_string(first_model, "model", model);
+        }
+        updated = serde_json::to_string_pretty(&json)
+            .map_err(|e| format!("Failed to serialize droid settings: {}", e))?;
+    }
+
+    plan.write(&path, updated);
+    Ok(Staged::Files { paths: vec![path], warnings: normalized.warnings })
+}
+
+fn stage_opencode(plan: &mut EditPlan, profile: &ProviderProfile) -> Result<Staged, String> {
+    if !profile.protocol.serves(Protocol::Openai) {
+        return Ok(Staged::Skipped(
+            "Profile does not expose an OpenAI-compatible endpoint".to_string(),
+        ));
+    }
+
+    let path = get_opencode_config_path()?;
+    if let Some(skipped) = missing(&path) {
+        return Ok(skipped);
+    }
+
+    let normalized = normalize_url(&profile.base_url, UrlStyle::Openai)?;
+    let config = CodexConfig {
+        base_url: normalized.url,
+        api_key: profile.api_key.clone(),
+        token_fingerprint: None,
+    };
+    let mut updated = render_opencode_config(&plan.read(&path)?, &config)?;
+
+    if let Some(model) = &profile.model {
+        let mut json: Value = serde_json::from_str(&updated)
+            .map_err(|e| format!("Failed to parse opencode config: {}", redacted(e)))?;
+        set_json_string(&mut json, "model", &format!("openai/{}", model));
+        updated = serde_json::to_string_pretty(&json)
+            .map_err(|e| format!("Failed to serialize opencode config: {}", e))?;
+    }
+
+    plan.write(&path, updated);
+    Ok(Staged::Files { paths: vec![path], warnings: normalized.warnings })
+}
+
+fn stage_tool(plan: &mut EditPlan, tool: Tool, profile: &ProviderProfile) -> Result<Staged, String> {
+    match tool {
+        Tool::Claude => stage_claude(plan, profile),
+        Tool::Codex => stage_codex(plan, profile),
+        Tool::Droid => stage_droid(plan, profile),
+        Tool::Opencode => stage_opencode(plan, profile),
+    }
+}
+
+/// Writes the profile to a single tool and returns what changed, which is
+/// nothing when the tool already matches. Fails when the tool cannot take
+/// the profile.
+pub(crate) fn apply_profile_to_tool(tool: Tool, profile: &ProviderProfile) -> Result<Vec<FileDiff>, String> {
+    let mut plan = EditPlan::default();
+    let paths = match stage_tool(&mut plan, tool, profile)? {
+        Staged::Files { paths, .. } => paths,
+        Staged::Skipped(reason) => return Err(reason),
+    };
+
+    let diffs = plan.diffs_for(&paths);
+    if !diffs.is_empty() {
+        plan.commit()?;
+        bump_generation();
+    }
+    Ok(diffs)
+}
+
+/// Writes one provider profile to every installed tool. All edits are staged
+/// first; if any tool fails, nothing is written.
+#[tauri::command]
+pub fn apply_profile_everywhere(profile: ProviderProfile, dry_run: bool) -> Result<ApplyReport, String> {
+    let profile = profile.switchable()?;
+    if profile.base_url.trim().is_empty() || profile.api_key.expose().trim().is_empty() {
+        return Err("Profile base URL and API key are required".to_string());
+    }
+
+    let mut profile = profile.resolved()?;
+    let mut key_warnings = Vec::new();
+    profile.api_key =
+        checked_token(profile.api_key.expose(), profile.protocol, &profile.base_url, &mut key_warnings)?.into();
+    let mut plan = EditPlan::default();
+    let mut results = Vec::new();
+
+    for tool in Tool::ALL {
+        let result = match stage_tool(&mut plan, tool, &profile) {
+            Ok(Staged::Files { paths, warnings }) => {
+                let diffs = plan.diffs_for(&paths);
+                let status = if diffs.is_empty() { ApplyStatus::Unchanged } else { ApplyStatus::Applied };
+                let warnings = key_warnings.iter().cloned().chain(warnings).collect();
+                ToolApplyResult { tool, status, message: None, warnings, diffs }
+            }
+            Ok(Staged::Skipped(reason)) => ToolApplyResult {
+                tool,
+                status: ApplyStatus::Skipped,
+                message: Some(reason),
+                warnings: Vec::new(),
+                diffs: Vec::new(),
+            },
+            Err(e) => ToolApplyResult {
+                tool,
+                status: ApplyStatus::Failed,
+                message: Some(e),
+                warnings: Vec::new(),
+                diffs: Vec::new(),
+            },
+        };
+        results.push(result);
+    }
+
+    let failed = results.iter().any(|r| r.status == ApplyStatus::Failed);
+    let committed = !dry_run && !failed;
+
+    if committed {
+        plan.commit()?;
+        bump_generation();
+        if !profile.id.is_empty() {
+            let applied = results.iter().filter(|r| r.status != ApplyStatus::Skipped).map(|r| r.tool);
+            retarget(applied, &profile.id);
+        }
+    }
+
+    Ok(ApplyReport { committed, results })
+}
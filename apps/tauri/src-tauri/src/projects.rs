@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::detect::git_output;
+use crate::edits::{EditPlan, FileDiff};
+use crate::lock::require_switch_allowed;
+use crate::paths::get_claude_state_path;
+use crate::perms::write_private;
+use crate::profiles::{Protocol, ProviderProfile};
+use crate::redact::redacted;
+use crate::urls::{normalize_url, UrlStyle};
+
+/// `env` keys in project settings that take precedence over the user-level
+/// switch. Claude Code merges project settings over `~/.claude/settings.json`
+/// and the shell environment.
+const SHADOWING_ENV_KEYS: [&str; 5] = [
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_API_KEY",
+    "ANTHROPIC_MODEL",
+    "CLAUDE_CODE_USE_BEDROCK",
+];
+
+/// Top-level settings keys that shadow the switch the same way.
+const SHADOWING_KEYS: [&str; 2] = ["model", "apiKeyHelper"];
+
+const PROJECT_SETTINGS_FILES: [&str; 2] = ["settings.json", "settings.local.json"];
+
+#[derive(Serialize)]
+pub struct ProjectApplyReport {
+    pub path: String,
+    pub committed: bool,
+    pub warnings: Vec<String>,
+    pub diffs: Vec<FileDiff>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectOverride {
+    pub project: String,
+    pub file: String,
+    /// Keys that shadow the global switch. Values are not reported, since
+    /// some of them are credentials.
+    pub keys: Vec<String>,
+    pub base_url: Option<String>,
+}
+
+fn local_settings_path(project: &Path) -> PathBuf {
+    project.join(".claude").join("settings.local.json")
+}
+
+fn project_dir(project: &str) -> Result<PathBuf, String> {
+    let path = fs::canonicalize(project).map_err(|e| format!("Failed to resolve {}: {}", project, e))?;
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    Ok(path)
+}
+
+fn parse_settings(content: &str, path: &Path) -> Result<Value, String> {
+    if content.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    serde_json::from_str(content).map_err(|e| format!("Failed to parse {}: {}", path.display(), redacted(e)))
+}
+
+fn env_object(json: &mut Value) -> Option<&mut Map<String, Value>> {
+    let obj = json.as_object_mut()?;
+    let env = obj
+        .entry("env".to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    env.as_object_mut()
+}
+
+/// settings.local.json holds the token, so it must stay out of git. Claude
+/// Code ignores it when it creates the file, but not when we do.
+fn ignore_warning(project: &Path, path: &Path) -> Option<String> {
+    git_output(project, &["rev-parse", "--is-inside-work-tree"]).ok()?;
+    let relative = path.strip_prefix(project).ok()?.display().to_string();
+    if git_output(project, &["check-ignore", "-q", "--", &relative]).is_ok() {
+        return None;
+    }
+    Some(format!(
+        "{} is not ignored by git and contains the profile's token",
+        relative
+    ))
+}
+
+/// Writes the profile into `<project>/.claude/settings.local.json`, so the
+/// project uses it regardless of the global switch.
+#[tauri::command]
+pub fn apply_profile_to_project(
+    project: String,
+    profile: ProviderProfile,
+    dry_run: bool,
+) -> Result<ProjectApplyReport, String> {
+    let profile = profile.switchable()?;
+    if !profile.protocol.serves(Protocol::Anthropic) {
+        return Err("Profile does not expose an Anthropic-compatible endpoint".to_string());
+    }
+    if profile.base_url.trim().is_empty() || profile.api_key.expose().trim().is_empty() {
+        return Err("Profile base URL and API key are required".to_string());
+    }
+
+    let profile = profile.resolved()?;
+    let project = project_dir(&project)?;
+    let path = local_settings_path(&project);
+    let normalized = normalize_url(&profile.base_url, UrlStyle::Anthropic)?;
+
+    let mut plan = EditPlan::default();
+    let content = if path.exists() { plan.read(&path)? } else { String::new() };
+    let mut json = parse_settings(&content, &path)?;
+
+    let env = env_object(&mut json)
+        .ok_or_else(|| format!("{} has a non-object env block", path.display()))?;
+    env.insert("ANTHROPIC_BASE_URL".to_string(), Value::String(normalized.url));
+    env.insert("ANTHROPIC_AUTH_TOKEN".to_string(), Value::String(profile.api_key.expose().to_string()));
+    // A key from the global environment would be sent alongside the token
+    env.remove("ANTHROPIC_API_KEY");
+
+    if let Some(model) = &profile.model {
+        if let Some(obj) = json.as_object_mut() {
+            obj.insert("model".to_string(), Value::String(model.clone()));
+        }
+    }
+
+    let pretty_json = serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("Failed to serialize claude settings: {}", e))?;
+    plan.write(&path, pretty_json);
+
+    let diffs = plan.diffs_for(std::slice::from_ref(&path));
+    let mut warnings = normalized.warnings;
+    warnings.extend(ignore_warning(&project, &path));
+
+    let committed = !dry_run && !diffs.is_empty();
+    if committed {
+        plan.commit()?;
+    }
+
+    Ok(ProjectApplyReport {
+        path: path.display().to_string(),
+        committed,
+        warnings,
+        diffs,
+    })
+}
+
+/// Removes the keys `apply_profile_to_project` writes, so the project follows
+/// the global switch again. Other local settings are kept.
+#[tauri::command]
+pub fn clear_project_profile(project: String) -> Result<(), String> {
+    require_switch_allowed()?;
+    let project = project_dir(&project)?;
+    let path = local_settings_path(&project);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut json = parse_settings(&content, &path)?;
+
+    if let Some(obj) = json.as_object_mut() {
+        obj.remove("model");
+        if let Some(env) = obj.get_mut("env").and_then(Value::as_object_mut) {
+            env.remove("ANTHROPIC_BASE_URL");
+            env.remove("ANTHROPIC_AUTH_TOKEN");
+        }
+    }
+
+    let pretty_json = serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("Failed to serialize claude settings: {}", e))?;
+    write_private(&path, pretty_json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Projects Claude Code has been opened in, from the keys of `projects` in
+/// its state file.
+pub(crate) fn known_projects() -> Vec<PathBuf> {
+    let Ok(path) = get_claude_state_path() else {
+        return Vec::new();
+    };
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<Value>(&content) else {
+        return Vec::new();
+    };
+
+    json.get("projects")
+        .and_then(Value::as_object)
+        .map(|projects| projects.keys().map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+fn project_override(project: &Path, file: &str) -> Option<ProjectOverride> {
+    let path = project.join(".claude").join(file);
+    let content = fs::read_to_string(&path).ok()?;
+    let json: Value = serde_json::from_str(&content).ok()?;
+    let env = json.get("env").and_then(Value::as_object);
+
+    let mut keys: Vec<String> = SHADOWING_KEYS
+        .iter()
+        .filter(|key| json.get(**key).is_some())
+        .map(|key| key.to_string())
+        .collect();
+    if let Some(env) = env {
+        keys.extend(
+            SHADOWING_ENV_KEYS
+                .iter()
+                .filter(|key| env.contains_key(**key))
+                .map(|key| format!("env.{}", key)),
+        );
+    }
+
+    if keys.is_empty() {
+        return None;
+    }
+
+    Some(ProjectOverride {
+        project: project.display().to_string(),
+        file: path.display().to_string(),
+        keys,
+        base_url: env
+            .and_then(|env| env.get("ANTHROPIC_BASE_URL"))
+            .and_then(Value::as_str)
+            .map(|s| s.to_string()),
+    })
+}
+
+fn holds_token(o: &ProjectOverride) -> bool {
+    o.keys.iter().any(|k| k == "env.ANTHROPIC_AUTH_TOKEN" || k == "env.ANTHROPIC_API_KEY")
+}
+
+/// Project settings files that hold a token, for the permissions audit.
+pub(crate) fn project_credential_files() -> Vec<PathBuf> {
+    list_project_overrides(Vec::new())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(holds_token)
+        .map(|o| PathBuf::from(o.file))
+        .collect()
+}
+
+/// The `settings.local.json` files of `projects` that hold a token and cannot
+/// be committed, because git ignores them or the project is not a work tree.
+/// These are the files `apply_profile_to_project` writes; a shared
+/// `settings.json` with a token is never one of them.
+pub(crate) fn managed_local_settings(projects: &[PathBuf]) -> Vec<PathBuf> {
+    projects
+        .iter()
+        .filter(|project| project_override(project, "settings.local.json").is_some_and(|o| holds_token(&o)))
+        .map(|project| (project, local_settings_path(project)))
+        .filter(|(project, path)| ignore_warning(project, path).is_none())
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Lists project settings files that will shadow the global switch. `extra`
+/// adds directories Claude Code has not recorded yet.
+#[tauri::command]
+pub fn list_project_overrides(extra: Vec<String>) -> Result<Vec<ProjectOverride>, String> {
+    let mut projects = known_projects();
+    for dir in extra {
+        projects.push(project_dir(&dir)?);
+    }
+    projects.sort();
+    projects.dedup();
+
+    Ok(projects
+        .iter()
+        .flat_map(|project| {
+            PROJECT_SETTINGS_FILES
+                .iter()
+                .filter_map(|file| project_override(project, file))
+        })
+        .collect())
+}
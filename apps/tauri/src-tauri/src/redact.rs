@@ -0,0 +1,180 @@
+use std::fmt;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::secret::Secret;
+use crate::secret_refs::is_reference;
+
+/// A stable identifier for a token that is safe to show and log: the first
+/// 12 hex digits of its SHA-256.
+pub(crate) fn fingerprint(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .take(6)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Shows a token's vendor prefix and last four characters, e.g. `sk-…a1b2`.
+/// Short tokens are hidden entirely, since the ends would give most of them away.
+pub(crate) fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() < 12 {
+        return "…".to_string();
+    }
+
+    let prefix_len = chars
+        .iter()
+        .take(8)
+        .position(|c| *c == '-' || *c == '_')
+        .map(|i| i + 1)
+        .unwrap_or(3);
+    let prefix: String = chars[..prefix_len].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+
+    format!("{}…{}", prefix, suffix)
+}
+
+/// Whether a value is a masked token sent back by the UI.
+pub(crate) fn is_masked(value: &str) -> bool {
+    value.contains('…')
+}
+
+/// The form read commands return a token field in. References name where
+/// the token lives rather than the token, so they are shown as they are.
+pub(crate) fn mask_value(value: &str) -> String {
+    if value.is_empty() || is_reference(value) {
+        value.to_string()
+    } else {
+        mask_token(value)
+    }
+}
+
+/// The fingerprint of a literal token field, if it holds one.
+pub(crate) fn value_fingerprint(value: &str) -> Option<String> {
+    if value.is_empty() || is_reference(value) {
+        None
+    } else {
+        Some(fingerprint(value))
+    }
+}
+
+/// Maps a token field as the UI saves it back to the value to store. The
+/// masked form stands for whichever stored token it was made from, so saving
+/// a form without touching its token keeps that token.
+pub(crate) fn unmask(value: &str, stored: &[Secret]) -> Result<String, String> {
+    if !is_masked(value) {
+        return Ok(value.to_string());
+    }
+
+    stored
+        .iter()
+        .map(Secret::expose)
+        .find(|token| !token.is_empty() && mask_value(token) == value)
+        .map(str::to_string)
+        .ok_or_else(|| "The token is masked and no longer matches the stored one; enter the full token".to_string())
+}
+
+/// Prefixes that mark a word as a key even when it is short.
+const KEY_PREFIXES: [&str; 3] = ["sk-", "gsk_", "ghp_"];
+/// Words this long that mix letters and digits are treated as tokens.
+const MIN_SECRET_LEN: usize = 20;
+
+fn looks_like_token(word: &str) -> bool {
+    if word.len() >= 12 && KEY_PREFIXES.iter().any(|p| word.starts_with(p)) {
+        return true;
+    }
+    word.len() >= MIN_SECRET_LEN
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Characters tokens are made of; anything else ends one.
+pub(crate) fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'
+}
+
+/// Masks everything in `text` that looks like a token, for messages built
+/// from parser errors, command output and other text that may quote one.
+pub(crate) fn redact_secrets(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, redacted: &mut String| {
+        if looks_like_token(word) {
+            redacted.push_str(&mask_token(word));
+        } else {
+            redacted.push_str(word);
+        }
+        word.zeroize();
+    };
+
+    for c in text.chars() {
+        if is_token_char(c) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut redacted);
+            redacted.push(c);
+        }
+    }
+    flush(&mut word, &mut redacted);
+    redacted
+}
+
+/// An error as text with tokens masked, for `map_err`.
+pub(crate) fn redacted(error: impl fmt::Display) -> String {
+    redact_secrets(&error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "sk-ant-api03-abcdef123456";
+
+    #[test]
+    fn fingerprints_are_short_and_stable() {
+        assert_eq!(fingerprint(TOKEN).len(), 12);
+        assert_eq!(fingerprint(TOKEN), fingerprint(TOKEN));
+        assert_ne!(fingerprint(TOKEN), fingerprint("sk-ant-api03-abcdef123457"));
+    }
+
+    #[test]
+    fn masks_keep_the_prefix_and_last_four() {
+        assert_eq!(mask_token(TOKEN), "sk-…3456");
+        assert_eq!(mask_token("abcdefghijklmnop"), "abc…mnop");
+        assert_eq!(mask_token("short"), "…");
+        assert!(is_masked(&mask_token(TOKEN)));
+    }
+
+    #[test]
+    fn references_are_shown_as_they_are() {
+        assert_eq!(mask_value("env:MY_TOKEN"), "env:MY_TOKEN");
+        assert_eq!(value_fingerprint("env:MY_TOKEN"), None);
+        assert_eq!(value_fingerprint(""), None);
+        assert_eq!(value_fingerprint(TOKEN), Some(fingerprint(TOKEN)));
+    }
+
+    #[test]
+    fn unmask_maps_a_masked_value_back_to_its_token() {
+        let stored = [Secret::new("sk-other-token-9999"), Secret::new(TOKEN)];
+        assert_eq!(unmask(&mask_token(TOKEN), &stored).unwrap(), TOKEN);
+        assert_eq!(unmask("sk-typed-new-token", &stored).unwrap(), "sk-typed-new-token");
+        assert!(unmask("sk-…0000", &stored).is_err());
+    }
+
+    #[test]
+    fn redact_secrets_masks_only_token_like_words() {
+        let text = format!("invalid key {} at line 3, see build-2024 docs", TOKEN);
+        assert_eq!(redact_secrets(&text), "invalid key sk-…3456 at line 3, see build-2024 docs");
+        assert_eq!(
+            redact_secrets("token=abc123def456ghi789jkl0"),
+            format!("token={}", mask_token("abc123def456ghi789jkl0"))
+        );
+        assert_eq!(redact_secrets("no secrets here"), "no secrets here");
+    }
+
+    #[test]
+    fn redacted_formats_errors() {
+        assert_eq!(redacted(format_args!("bad {}", TOKEN)), "bad sk-…3456");
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+use serde::Serialize;
+use ureq::Agent;
+
+use crate::edits::{EditPlan, FileDiff};
+use crate::leaks::{known_tokens, managed_files, KnownToken};
+use crate::lock::require_app_unlocked;
+use crate::profiles::Protocol;
+use crate::projects::project_credential_files;
+use crate::redact::{fingerprint, is_token_char, redacted};
+use crate::shell::bump_generation;
+use crate::urls::UrlStyle;
+use crate::validate::checked_token;
+use crate::vault::{entries_holding, group_entry, reference, replace_token, require_unlocked, restore_token};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Serialize)]
+pub struct KeyCheck {
+    pub url: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RotationReport {
+    pub committed: bool,
+    pub new_fingerprint: String,
+    /// Where the old token is managed, e.g. `profile work`.
+    pub sources: Vec<String>,
+    /// Planned file edits, with both tokens masked.
+    pub diffs: Vec<FileDiff>,
+    /// Vault entries that hold the old token.
+    pub vault_entries: Vec<String>,
+    pub check: Option<KeyCheck>,
+    /// Problems with the new token that do not stop the rotation.
+    pub warnings: Vec<String>,
+}
+
+/// Asks the provider to list its models with `key`. Gateways that do not
+/// serve a model list still answer, so only a refused key or no answer at
+/// all counts as a failure.
+fn check_key(known: &KnownToken, key: &str) -> KeyCheck {
+    let base = known.base_url.trim_end_matches('/');
+    let url = match known.style {
+        UrlStyle::Anthropic => format!("{}/v1/models", base),
+        UrlStyle::Openai => format!("{}/models", base),
+    };
+
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(CHECK_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let request = agent
+        .get(&url)
+        .header("Authorization", &format!("Bearer {}", key));
+    let request = match known.style {
+        UrlStyle::Anthropic => request.header("x-api-key", key).header("anthropic-version", ANTHROPIC_VERSION),
+        UrlStyle::Openai => request,
+    };
+
+    match request.call() {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            KeyCheck {
+                url,
+                ok: status != 401 && status != 403,
+                status: Some(status),
+                error: None,
+            }
+        }
+        Err(e) => KeyCheck {
+            url,
+            ok: false,
+            status: None,
+            error: Some(redacted(e)),
+        },
+    }
+}
+
+/// Replaces whole occurrences of `old` in `content`. A match inside a longer
+/// token, such as an old key that is a prefix of another, is left alone.
+fn replace_whole(content: &str, old: &str, new: &str) -> String {
+    let mut replaced = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(at) = rest.find(old) {
+        let end = at + old.len();
+        let before = rest[..at].chars().next_back().or_else(|| replaced.chars().next_back());
+        let after = rest[end..].chars().next();
+        replaced.push_str(&rest[..at]);
+        if before.is_some_and(is_token_char) || after.is_some_and(is_token_char) {
+            replaced.push_str(old);
+        } else {
+            replaced.push_str(new);
+        }
+        rest = &rest[end..];
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+/// Replaces the token with fingerprint `old_fingerprint` by `new_token` in
+/// every managed file and vault entry that holds it, all or nothing. With
+/// `check`, the new token is tried against the provider first and nothing is
+/// changed if it is refused. Runs off the main thread, since the check waits
+/// on the network.
+#[tauri::command(async)]
+pub fn rotate_token(
+    old_fingerprint: String,
+    new_token: String,
+    dry_run: bool,
+    check: bool,
+) -> Result<RotationReport, String> {
+    let new = new_token.trim().to_string();
+    if new.is_empty() {
+        return Err("The new token is required".to_string());
+    }
+    require_app_unlocked()?;
+    // Tokens sealed into the vault can only be found and replaced while it is open
+    require_unlocked()?;
+
+    let (tokens, _) = known_tokens();
+    let matching: Vec<&KnownToken> = tokens
+        .iter()
+        .filter(|t| fingerprint(t.token.expose()) == old_fingerprint.trim())
+        .collect();
+    let Some(known) = matching.first() else {
+        return Err(format!("No managed token has fingerprint {}", old_fingerprint.trim()));
+    };
+    let old = known.token.expose();
+    if old == new {
+        return Err("The new token is the same as the old one".to_string());
+    }
+
+    let protocol = match known.style {
+        UrlStyle::Anthropic => Protocol::Anthropic,
+        UrlStyle::Openai => Protocol::Openai,
+    };
+    let mut warnings = Vec::new();
+    let new = checked_token(&new, protocol, &known.base_url, &mut warnings)?;
+
+    let check = check.then(|| check_key(known, &new));
+    if let Some(check) = &check {
+        if !check.ok {
+            return Err(match (&check.status, &check.error) {
+                (Some(status), _) => format!("{} refused the new token with status {}", check.url, status),
+                (None, Some(e)) => format!("Failed to reach {}: {}", check.url, e),
+                (None, None) => format!("Failed to reach {}", check.url),
+            });
+        }
+    }
+
+    // Inactive groups refer to their vault entry by the token's fingerprint
+    let old_reference = reference(&group_entry(old));
+    let new_reference = reference(&group_entry(&new));
+
+    // Shared project settings are not managed for leak scans, but a token in
+    // them is still replaced
+    let mut files = managed_files();
+    files.extend(project_credential_files());
+    files.sort();
+    files.dedup();
+
+    let mut plan = EditPlan::default();
+    let mut paths = Vec::new();
+    for path in files.into_iter().filter(|p| p.exists()) {
+        let content = plan.read(&path)?;
+        let updated = replace_whole(&replace_whole(&content, old, &new), &old_reference, &new_reference);
+        if updated != content {
+            plan.write(&path, updated);
+            paths.push(path);
+        }
+    }
+
+    let vault_entries = entries_holding(old)?;
+    let diffs: Vec<FileDiff> = plan.diffs_for(&paths);
+
+    let committed = !dry_run && (!diffs.is_empty() || !vault_entries.is_empty());
+    if committed {
+        let moves = replace_token(old, &new)?;
+        if let Err(e) = plan.commit() {
+            let _ = restore_token(&moves, old);
+            return Err(e);
+        }
+        bump_generation();
+    }
+
+    Ok(RotationReport {
+        committed,
+        new_fingerprint: fingerprint(&new),
+        sources: matching.iter().map(|t| t.source.clone()).collect(),
+        diffs,
+        vault_entries,
+        check,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_values_are_replaced() {
+        let content = "export CS_AUTH_TOKEN=\"sk-old-key\"\n{\"apiKey\": \"sk-old-key\"}\nsk-old-key";
+        assert_eq!(
+            replace_whole(content, "sk-old-key", "sk-new-key"),
+            "export CS_AUTH_TOKEN=\"sk-new-key\"\n{\"apiKey\": \"sk-new-key\"}\nsk-new-key"
+        );
+    }
+
+    #[test]
+    fn longer_tokens_containing_the_old_one_are_kept() {
+        let content = "A=\"sk-old-key-2\" B=\"xsk-old-key\" C=\"sk-old-key\"";
+        assert_eq!(
+            replace_whole(content, "sk-old-key", "sk-new-key"),
+            "A=\"sk-old-key-2\" B=\"xsk-old-key\" C=\"sk-new-key\""
+        );
+    }
+
+    #[test]
+    fn adjacent_matches_are_not_whole_values() {
+        assert_eq!(replace_whole("abcabc abc", "abc", "xyz"), "abcabc xyz");
+    }
+}
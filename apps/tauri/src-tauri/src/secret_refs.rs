@@ -0,0 +1,280 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+
+use crate::detect::command_output;
+use crate::lock::require_app_unlocked;
+use crate::paths::expand_home;
+use crate::redact::{fingerprint, mask_token, redacted};
+use crate::secret::Secret;
+use crate::settings::read_app_settings;
+use crate::vault::{lookup_token, VAULT_PREFIX};
+
+const ENV_PREFIX: &str = "env:";
+const FILE_PREFIX: &str = "file:";
+const CMD_PREFIX: &str = "cmd:";
+const OP_PREFIX: &str = "op://";
+
+/// Follows the token of an active export that was resolved from a reference,
+/// so the reference outlives the switch: `export KEY="token" # my-switch:ref cmd:…`.
+const REF_MARKER: &str = "# my-switch:ref ";
+
+const CMD_TIMEOUT: Duration = Duration::from_secs(10);
+/// `op` may wait for the desktop app to approve the request.
+const OP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Commands and password managers can be slow or prompt, so their results
+/// are reused for a while. Vault and environment lookups are not cached.
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Reference, token and when it was resolved. Both are kept as `Secret`s,
+/// since a `cmd:` reference may carry a token, so entries are wiped when
+/// they expire or are evicted and when the app locks.
+static CACHE: Mutex<Vec<(Secret, Secret, Instant)>> = Mutex::new(Vec::new());
+
+#[derive(Serialize)]
+pub struct ResolvedReference {
+    pub masked: String,
+    pub fingerprint: String,
+}
+
+/// Whether a token field holds a reference rather than the token itself.
+pub(crate) fn is_reference(value: &str) -> bool {
+    [VAULT_PREFIX, ENV_PREFIX, FILE_PREFIX, CMD_PREFIX, OP_PREFIX]
+        .iter()
+        .any(|prefix| value.starts_with(prefix))
+}
+
+/// Splits the value of an export line, everything after `KEY=`, into the
+/// token and the reference it was resolved from, if it was.
+pub(crate) fn split_export_value(raw: &str) -> (&str, Option<&str>) {
+    match raw.split_once(REF_MARKER) {
+        Some((value, reference)) => (value.trim().trim_matches('"'), Some(reference.trim())),
+        None => (raw.trim().trim_matches('"'), None),
+    }
+}
+
+/// The value of an export line for `token`, remembering `reference`.
+pub(crate) fn export_value(token: &str, reference: Option<&str>) -> String {
+    match reference {
+        Some(reference) => format!("\"{}\" {}{}", token, REF_MARKER, reference),
+        None => format!("\"{}\"", token),
+    }
+}
+
+/// Whether resolving a reference runs a program, which may be slow or prompt.
+pub(crate) fn runs_command(value: &str) -> bool {
+    value.starts_with(CMD_PREFIX) || value.starts_with(OP_PREFIX)
+}
+
+fn cached(reference: &str) -> Option<String> {
+    let mut cache = CACHE.lock().ok()?;
+    cache.retain(|(_, _, at)| at.elapsed() < CACHE_TTL);
+    cache
+        .iter()
+        .find(|(r, _, _)| r.expose() == reference)
+        .map(|(_, value, _)| value.expose().to_string())
+}
+
+/// The cached result of a command or `op` reference, without running it.
+pub(crate) fn cached_token(reference: &str) -> Option<String> {
+    cached(reference)
+}
+
+fn remember(reference: &str, value: &str) {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.retain(|(r, _, at)| r.expose() != reference && at.elapsed() < CACHE_TTL);
+        cache.push((Secret::new(reference), Secret::new(value), Instant::now()));
+    }
+}
+
+pub(crate) fn clear_cache() {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.clear();
+    }
+}
+
+fn shell_command(script: &str) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(script);
+    command
+}
+
+/// The 1Password CLI, or the stand-in configured in the app settings.
+fn op_binary() -> String {
+    read_app_settings()
+        .ok()
+        .and_then(|s| s.op_path)
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "op".to_string())
+}
+
+/// Reads an `op://` reference with the given 1Password CLI.
+fn op_read(binary: &str, reference: &str) -> Result<String, String> {
+    let mut command = Command::new(binary);
+    command.arg("read").arg(reference);
+    run(reference, command, OP_TIMEOUT)
+}
+
+fn run(reference: &str, command: Command, timeout: Duration) -> Result<String, String> {
+    if let Some(value) = cached(reference) {
+        return Ok(value);
+    }
+    let value = command_output(command, timeout)?;
+    remember(reference, &value);
+    Ok(value)
+}
+
+fn resolve(value: &str) -> Result<String, String> {
+    if let Some(id) = value.strip_prefix(VAULT_PREFIX) {
+        return lookup_token(id);
+    }
+    if let Some(name) = value.strip_prefix(ENV_PREFIX) {
+        return env::var(name.trim())
+            .map_err(|_| format!("Environment variable {} is not set", name.trim()));
+    }
+    if let Some(path) = value.strip_prefix(FILE_PREFIX) {
+        let path = expand_home(path.trim())?;
+        return fs::read_to_string(&path)
+            .map(|content| content.trim().to_string())
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    }
+    if let Some(script) = value.strip_prefix(CMD_PREFIX) {
+        return run(value, shell_command(script.trim()), CMD_TIMEOUT);
+    }
+    if value.starts_with(OP_PREFIX) {
+        return op_read(&op_binary(), value);
+    }
+    Ok(value.to_string())
+}
+
+/// Returns the token a field stands for. Literal tokens are returned as
+/// they are; references are looked up only now, when a concrete value is
+/// about to be written.
+pub(crate) fn resolve_token(value: &str) -> Result<String, String> {
+    if !is_reference(value) {
+        return Ok(value.to_string());
+    }
+
+    // Inline commands may carry a token of their own
+    let token = resolve(value).map_err(|e| redacted(format!("Failed to resolve {}: {}", value, e)))?;
+    if token.is_empty() {
+        return Err(redacted(format!("Failed to resolve {}: it is empty", value)));
+    }
+    Ok(token)
+}
+
+/// Resolves a reference without using it, so the UI can check it. Only
+/// a masked form and the fingerprint are returned. Commands and `op` may
+/// take a while, so it runs off the main thread.
+#[tauri::command(async)]
+pub fn check_secret_reference(reference: String) -> Result<ResolvedReference, String> {
+    require_app_unlocked()?;
+    let token = resolve_token(reference.trim())?;
+    Ok(ResolvedReference {
+        masked: mask_token(&token),
+        fingerprint: fingerprint(&token),
+    })
+}
+
+/// Forgets cached command and password-manager results, e.g. after rotating a key.
+#[tauri::command]
+pub fn clear_secret_cache() {
+    clear_cache();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_tokens_pass_through() {
+        assert_eq!(resolve_token("sk-literal-token").unwrap(), "sk-literal-token");
+        assert!(!is_reference("sk-literal-token"));
+    }
+
+    #[test]
+    fn env_references_read_the_variable() {
+        // PATH is always set, so the test does not have to change the environment
+        assert_eq!(resolve_token("env:PATH").unwrap(), env::var("PATH").unwrap());
+        assert!(resolve_token("env:MY_SWITCH_TEST_UNSET_TOKEN").is_err());
+    }
+
+    #[test]
+    fn file_references_read_the_trimmed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key");
+        fs::write(&path, "  from-file\n").unwrap();
+
+        let reference = format!("file:{}", path.display());
+        assert_eq!(resolve_token(&reference).unwrap(), "from-file");
+        assert!(resolve_token(&format!("file:{}", dir.path().join("missing").display())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cmd_references_run_the_command() {
+        assert_eq!(resolve_token("cmd:echo from-cmd").unwrap(), "from-cmd");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_commands_report_their_exit() {
+        let error = resolve_token("cmd:echo nope >&2; exit 3").unwrap_err();
+        assert!(error.contains("exited"), "{}", error);
+        assert!(error.contains("nope"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn empty_output_is_an_error() {
+        let error = resolve_token("cmd:true").unwrap_err();
+        assert!(error.contains("it is empty"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn slow_commands_time_out() {
+        let error = run("cmd:sleep 5", shell_command("sleep 5"), Duration::from_millis(200)).unwrap_err();
+        assert!(error.contains("timed out"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_results_are_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("runs");
+        let reference = format!("cmd:echo run >> {}; echo cached-token", counter.display());
+
+        assert_eq!(resolve_token(&reference).unwrap(), "cached-token");
+        assert_eq!(resolve_token(&reference).unwrap(), "cached-token");
+        assert_eq!(cached_token(&reference).as_deref(), Some("cached-token"));
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn op_references_use_the_given_cli() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let op = dir.path().join("op");
+        fs::write(&op, "#!/bin/sh\n[ \"$1\" = read ] && echo \"stand-in $2\"\n").unwrap();
+        fs::set_permissions(&op, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(
+            op_read(&op.display().to_string(), "op://team/gateway/key").unwrap(),
+            "stand-in op://team/gateway/key"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+
+use crate::lock::require_app_unlocked;
+use crate::paths::get_app_settings_path;
+use crate::redact::redacted;
+
+/// Explicit locations that take precedence over the tools' own environment
+/// variables and defaults. Empty values are ignored.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct PathOverrides {
+    pub secrets_file: Option<String>,
+    pub claude_config_dir: Option<String>,
+    pub codex_home: Option<String>,
+    pub droid_dir: Option<String>,
+    pub opencode_config_dir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    pub paths: PathOverrides,
+    /// Seconds between background consistency checks. None uses the
+    /// default interval and 0 turns the check off.
+    pub drift_check_secs: Option<u64>,
+    /// The 1Password CLI used for `op://` references. Defaults to `op` on PATH.
+    pub op_path: Option<String>,
+    /// Extra files and directories the leak scanner searches, besides shell
+    /// history and the projects Claude Code knows.
+    pub leak_scan_paths: Vec<String>,
+    /// Minutes without activity before the app locks. None uses the default
+    /// period and 0 turns auto-lock off.
+    pub auto_lock_minutes: Option<u64>,
+    /// Lets switching between existing profiles go on while the app is locked.
+    pub switch_while_locked: bool,
+}
+
+pub(crate) fn read_app_settings() -> Result<AppSettings, String> {
+    let path = get_app_settings_path()?;
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read app settings: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse app settings: {}", redacted(e)))
+}
+
+pub(crate) fn write_app_settings(settings: &AppSettings) -> Result<(), String> {
+    let path = get_app_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app settings directory: {}", e))?;
+    }
+
+    let pretty_json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize app settings: {}", e))?;
+
+    fs::write(&path, pretty_json)
+        .map_err(|e| format!("Failed to write app settings: {}", e))
+}
+
+#[tauri::command]
+pub fn get_app_settings() -> Result<AppSettings, String> {
+    read_app_settings()
+}
+
+#[tauri::command]
+pub fn save_app_settings(settings: AppSettings) -> Result<(), String> {
+    // Otherwise a locked app could be talked into allowing more
+    require_app_unlocked()?;
+    write_app_settings(&settings)
+}
@@ -0,0 +1,380 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{get_generation_path, get_secrets_path};
+
+pub(crate) const BLOCK_START: &str = "# >>> my-switch >>>";
+pub(crate) const BLOCK_END: &str = "# <<< my-switch <<<";
+const HOOK_NAME: &str = "__my_switch_reload";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+#[derive(Serialize)]
+pub struct ShellStatus {
+    pub shell: Shell,
+    pub rc_path: String,
+    pub rc_exists: bool,
+    /// Whether the rc file sources the secrets file, by any means.
+    pub sourced: bool,
+    /// Whether the my-switch managed block is present.
+    pub managed: bool,
+    /// Whether the rc file installs the hook that reloads switched credentials.
+    pub reload_hook: bool,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Zsh, Shell::Bash, Shell::Fish];
+
+    fn rc_path(self) -> Result<PathBuf, String> {
+        let home = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
+        let env_dir = |key: &str| env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        Ok(match self {
+            Shell::Zsh => env_dir("ZDOTDIR").unwrap_or(home).join(".zshrc"),
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Fish => env_dir("XDG_CONFIG_HOME")
+                .unwrap_or_else(|| home.join(".config"))
+                .join("fish")
+                .join("config.fish"),
+        })
+    }
+}
+
+fn home_relative(path: &Path) -> Option<String> {
+    let home = dirs::home_dir()?;
+    path.strip_prefix(home).ok().map(|rel| rel.display().to_string())
+}
+
+/// The ways a rc file might spell the secrets path.
+fn path_spellings(secrets: &Path) -> Vec<String> {
+    let mut spellings = vec![secrets.display().to_string()];
+    if let Some(rel) = home_relative(secrets) {
+        spellings.push(format!("~/{}", rel));
+        spellings.push(format!("$HOME/{}", rel));
+        spellings.push(format!("${{HOME}}/{}", rel));
+    }
+    spellings
+}
+
+fn sources_secrets(content: &str, secrets: &Path) -> bool {
+    let spellings = path_spellings(secrets);
+    content.lines().any(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return false;
+        }
+        let sources = line.starts_with("source ")
+            || line.starts_with(". ")
+            || line.contains(" source ")
+            || line.contains(" . ");
+        sources && spellings.iter().any(|s| line.contains(s.as_str()))
+    })
+}
+
+/// Byte range of the managed block, from its start marker to the end of its end marker.
+pub(crate) fn find_block(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(BLOCK_START)?;
+    let end = content[start..].find(BLOCK_END)? + start + BLOCK_END.len();
+    Some((start, end))
+}
+
+/// Returns the content with the managed block removed, or None if there is no block.
+pub(crate) fn strip_block(content: &str) -> Option<String> {
+    let (start, end) = find_block(content)?;
+
+    let before = content[..start].trim_end_matches('\n');
+    let after = content[end..].trim_start_matches('\n');
+
+    let mut stripped = before.to_string();
+    if !after.is_empty() {
+        if !stripped.is_empty() {
+            stripped.push('\n');
+        }
+        stripped.push_str(after);
+    }
+    if !stripped.is_empty() && !stripped.ends_with('\n') {
+        stripped.push('\n');
+    }
+    Some(stripped)
+}
+
+/// A path as written inside the block, relative to $HOME when possible.
+fn quoted_path(path: &Path) -> String {
+    let path = match home_relative(path) {
+        Some(rel) => format!("$HOME/{}", escape_double_quoted(&rel)),
+        None => escape_double_quoted(&path.display().to_string()),
+    };
+    format!("\"{}\"", path)
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    value
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '\\' | '$' | '`' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+/// A prompt hook that re-sources the secrets file when the generation stamp
+/// changes, so already-open shells pick up a switch at their next prompt.
+/// Reading the stamp uses shell builtins only, so the hook does not fork.
+fn render_hook(shell: Shell, secrets: &Path, stamp: &Path) -> String {
+    let secrets = quoted_path(secrets);
+    let stamp = quoted_path(stamp);
+
+    match shell {
+        Shell::Zsh => format!(
+            r#"__my_switch_generation=""
+[ -r {stamp} ] && read -r __my_switch_generation < {stamp}
+{HOOK_NAME}() {{
+  local generation=""
+  [ -r {stamp} ] && read -r generation < {stamp}
+  if [ "$generation" != "$__my_switch_generation" ]; then
+    __my_switch_generation="$generation"
+    [ -f {secrets} ] && . {secrets}
+  fi
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd {HOOK_NAME}"#
+        ),
+        Shell::Bash => format!(
+            r#"__my_switch_generation=""
+[ -r {stamp} ] && read -r __my_switch_generation < {stamp}
+{HOOK_NAME}() {{
+  local generation=""
+  [ -r {stamp} ] && read -r generation < {stamp}
+  if [ "$generation" != "$__my_switch_generation" ]; then
+    __my_switch_generation="$generation"
+    [ -f {secrets} ] && . {secrets}
+  fi
+}}
+case ";$PROMPT_COMMAND;" in
+  *";{HOOK_NAME};"*) ;;
+  *) PROMPT_COMMAND="{HOOK_NAME}${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}" ;;
+esac"#
+        ),
+        Shell::Fish => format!(
+            r#"set -g __my_switch_generation ""
+test -r {stamp}; and read -g __my_switch_generation < {stamp}
+function {HOOK_NAME} --on-event fish_prompt
+    set -l generation ""
+    test -r {stamp}; and read generation < {stamp}
+    if test "$generation" != "$__my_switch_generation"
+        set -g __my_switch_generation $generation
+        test -f {secrets}; and source {secrets}
+    end
+end"#
+        ),
+    }
+}
+
+fn render_block(shell: Shell, secrets: &Path, reload_hook: Option<&Path>) -> String {
+    let path = quoted_path(secrets);
+    let mut body = match shell {
+        Shell::Zsh | Shell::Bash => format!("[ -f {path} ] && . {path}"),
+        Shell::Fish => format!("if test -f {path}\n    source {path}\nend"),
+    };
+
+    if let Some(stamp) = reload_hook {
+        body.push('\n');
+        body.push_str(&render_hook(shell, secrets, stamp));
+    }
+
+    format!(
+        "{}\n# Managed by my-switch. Remove it from the app or delete this block.\n{}\n{}\n",
+        BLOCK_START, body, BLOCK_END
+    )
+}
+
+/// Records that the active credentials changed. Shells running the reload
+/// hook re-source the secrets file at their next prompt. This is best effort:
+/// a failed bump only means open shells keep the old values until restarted.
+pub(crate) fn bump_generation() {
+    let Ok(path) = get_generation_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    let _ = fs::write(&path, format!("{}\n", stamp));
+}
+
+fn backup(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("rc");
+    let backup_path = path.with_file_name(format!("{}.my-switch-{}.bak", file_name, stamp));
+
+    fs::copy(path, &backup_path)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))
+}
+
+fn read_rc(path: &Path) -> Result<String, String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn write_rc(path: &Path, content: &str) -> Result<(), String> {
+    backup(path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn shell_status(shell: Shell) -> Result<ShellStatus, String> {
+    let rc_path = shell.rc_path()?;
+    let secrets = get_secrets_path()?;
+    let content = read_rc(&rc_path)?;
+
+    Ok(ShellStatus {
+        shell,
+        rc_exists: rc_path.exists(),
+        rc_path: rc_path.display().to_string(),
+        sourced: sources_secrets(&content, &secrets),
+        managed: content.contains(BLOCK_START),
+        reload_hook: content.contains(HOOK_NAME),
+    })
+}
+
+#[tauri::command]
+pub fn shell_integration_status() -> Result<Vec<ShellStatus>, String> {
+    Shell::ALL.into_iter().map(shell_status).collect()
+}
+
+/// Adds the managed block to the shell's rc file, or refreshes it in place.
+/// Without the reload hook, does nothing if the rc file already sources the
+/// secrets file on its own.
+#[tauri::command]
+pub fn install_shell_integration(shell: Shell, reload_hook: bool) -> Result<ShellStatus, String> {
+    let rc_path = shell.rc_path()?;
+    let secrets = get_secrets_path()?;
+    let stamp = get_generation_path()?;
+    let hook = reload_hook.then_some(stamp.as_path());
+    let content = read_rc(&rc_path)?;
+
+    let updated = match find_block(&content) {
+        Some((start, end)) => {
+            let outside = format!("{}{}", &content[..start], &content[end..]);
+            if !reload_hook && sources_secrets(&outside, &secrets) {
+                // The user sources it themselves now, so the block is redundant
+                strip_block(&content).unwrap_or(outside)
+            } else {
+                let block = render_block(shell, &secrets, hook);
+                format!("{}{}{}", &content[..start], block.trim_end_matches('\n'), &content[end..])
+            }
+        }
+        None if !reload_hook && sources_secrets(&content, &secrets) => content.clone(),
+        None => format!("{}{}", separated(&content), render_block(shell, &secrets, hook)),
+    };
+
+    if updated != content {
+        write_rc(&rc_path, &updated)?;
+    }
+
+    shell_status(shell)
+}
+
+#[tauri::command]
+pub fn remove_shell_integration(shell: Shell) -> Result<ShellStatus, String> {
+    let rc_path = shell.rc_path()?;
+    let content = read_rc(&rc_path)?;
+
+    if let Some(stripped) = strip_block(&content) {
+        write_rc(&rc_path, &stripped)?;
+    }
+
+    shell_status(shell)
+}
+
+/// Ensures a blank line between existing content and an appended block.
+pub(crate) fn separated(content: &str) -> String {
+    if content.trim().is_empty() {
+        String::new()
+    } else {
+        format!("{}\n\n", content.trim_end_matches('\n'))
+    }
+}
+
+/// The reload hook on its own, for users who manage their rc files by hand.
+#[tauri::command]
+pub fn shell_hook_snippet(shell: Shell) -> Result<String, String> {
+    Ok(render_hook(shell, &get_secrets_path()?, &get_generation_path()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(body: &str) -> String {
+        format!("{}\n{}\n{}", BLOCK_START, body, BLOCK_END)
+    }
+
+    #[test]
+    fn find_block_spans_both_markers() {
+        let content = format!("alias ll='ls -l'\n{}\nexport PATH=\"$PATH\"\n", block("source x"));
+        let (start, end) = find_block(&content).unwrap();
+        assert_eq!(&content[start..end], block("source x"));
+    }
+
+    #[test]
+    fn find_block_needs_both_markers() {
+        assert_eq!(find_block("alias ll='ls -l'\n"), None);
+        assert_eq!(find_block(&format!("{}\nsource x\n", BLOCK_START)), None);
+        assert_eq!(find_block(&format!("{}\n{}\n", BLOCK_END, BLOCK_START)), None);
+    }
+
+    #[test]
+    fn strip_block_keeps_the_surrounding_lines() {
+        let content = format!("alias ll='ls -l'\n\n{}\n\nexport EDITOR=vim\n", block("source x"));
+        assert_eq!(strip_block(&content).unwrap(), "alias ll='ls -l'\nexport EDITOR=vim\n");
+    }
+
+    #[test]
+    fn strip_block_of_a_block_alone_is_empty() {
+        assert_eq!(strip_block(&format!("{}\n", block("source x"))).unwrap(), "");
+        assert_eq!(strip_block("alias ll='ls -l'\n"), None);
+    }
+
+    #[test]
+    fn appending_then_stripping_restores_the_file() {
+        let original = "alias ll='ls -l'\n";
+        let appended = format!("{}{}\n", separated(original), block("source x"));
+        assert_eq!(strip_block(&appended).unwrap(), original);
+    }
+
+    #[test]
+    fn commented_sources_do_not_count() {
+        let secrets = Path::new("/etc/my-switch/secrets");
+        assert!(sources_secrets("source /etc/my-switch/secrets\n", secrets));
+        assert!(sources_secrets("[ -f x ] && . /etc/my-switch/secrets\n", secrets));
+        assert!(!sources_secrets("# source /etc/my-switch/secrets\n", secrets));
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{
+    get_claude_settings_path, get_codex_auth_path, get_codex_config_path, get_droid_settings_path,
+    get_opencode_config_path, get_secrets_path,
+};
+
+/// The CLI coding tools whose configuration my-switch manages.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    Claude,
+    Codex,
+    Droid,
+    Opencode,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [Tool::Claude, Tool::Codex, Tool::Droid, Tool::Opencode];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Tool::Claude => "Claude Code",
+            Tool::Codex => "Codex",
+            Tool::Droid => "Droid",
+            Tool::Opencode => "OpenCode",
+        }
+    }
+
+    pub fn binary_name(self) -> &'static str {
+        match self {
+            Tool::Claude => "claude",
+            Tool::Codex => "codex",
+            Tool::Droid => "droid",
+            Tool::Opencode => "opencode",
+        }
+    }
+
+    /// The files my-switch reads and writes on behalf of this tool.
+    pub fn config_files(self) -> Result<Vec<PathBuf>, String> {
+        Ok(match self {
+            Tool::Claude => vec![get_secrets_path()?, get_claude_settings_path()?],
+            Tool::Codex => vec![get_codex_config_path()?, get_codex_auth_path()?],
+            Tool::Droid => vec![get_droid_settings_path()?],
+            Tool::Opencode => vec![get_opencode_config_path()?],
+        })
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// How a tool expects its base URL to look.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlStyle {
+    /// The bare API root. Claude Code appends `/v1/messages` itself.
+    Anthropic,
+    /// An OpenAI-style root ending in a version segment such as `/v1`.
+    Openai,
+}
+
+#[derive(Serialize)]
+pub struct NormalizedUrl {
+    pub url: String,
+    pub changed: bool,
+    pub warnings: Vec<String>,
+}
+
+// Endpoint paths that are sometimes pasted together with the base URL
+const ENDPOINT_SUFFIXES: [&[&str]; 4] = [
+    &["chat", "completions"],
+    &["completions"],
+    &["responses"],
+    &["messages"],
+];
+
+fn is_version_segment(segment: &str) -> bool {
+    segment.len() > 1
+        && segment.starts_with('v')
+        && segment[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_local_host(host: &str) -> bool {
+    host == "localhost" || host == "127.0.0.1" || host == "[::1]"
+}
+
+/// Validates the scheme and host of `raw` and rewrites its path to the
+/// suffix `style` expects. Fixes that can be made safely are applied and
+/// reported as warnings; anything ambiguous is only flagged.
+pub fn normalize_url(raw: &str, style: UrlStyle) -> Result<NormalizedUrl, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("Base URL is empty".to_string());
+    }
+
+    let mut url = Url::parse(trimmed)
+        .map_err(|e| format!("Invalid base URL \"{}\": {}", trimmed, e))?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("Unsupported URL scheme \"{}\", expected http or https", url.scheme()));
+    }
+
+    let host = match url.host_str() {
+        Some(host) if !host.is_empty() => host.to_string(),
+        _ => return Err(format!("Base URL \"{}\" has no host", trimmed)),
+    };
+
+    let mut warnings = Vec::new();
+
+    if url.scheme() == "http" && !is_local_host(&host) {
+        warnings.push("Base URL uses plain http, so the API key is sent unencrypted".to_string());
+    }
+
+    if url.query().is_some() {
+        url.set_query(None);
+        warnings.push("Removed query string from base URL".to_string());
+    }
+    if url.fragment().is_some() {
+        url.set_fragment(None);
+        warnings.push("Removed fragment from base URL".to_string());
+    }
+
+    let mut segments: Vec<String> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+
+    for suffix in ENDPOINT_SUFFIXES {
+        if segments.len() > suffix.len()
+            && segments[segments.len() - suffix.len()..].iter().zip(suffix).all(|(a, b)| a == b)
+        {
+            segments.truncate(segments.len() - suffix.len());
+            warnings.push(format!("Removed /{} endpoint path from base URL", suffix.join("/")));
+            break;
+        }
+    }
+
+    while segments.len() >= 2
+        && is_version_segment(&segments[segments.len() - 1])
+        && segments[segments.len() - 1] == segments[segments.len() - 2]
+    {
+        let version = segments.pop().unwrap_or_default();
+        warnings.push(format!("Removed duplicated /{} suffix", version));
+    }
+
+    match style {
+        UrlStyle::Anthropic => {
+            if segments.last().map(|s| s.as_str()) == Some("v1") {
+                segments.pop();
+                warnings.push("Removed /v1 suffix, the tool adds it itself".to_string());
+            }
+        }
+        UrlStyle::Openai => {
+            if segments.is_empty() {
+                segments.push("v1".to_string());
+                warnings.push("Added /v1 suffix expected by OpenAI-compatible tools".to_string());
+            } else if !segments.last().is_some_and(|s| is_version_segment(s)) {
+                warnings.push("Base URL does not end with a version segment such as /v1".to_string());
+            }
+        }
+    }
+
+    url.set_path(&segments.join("/"));
+    let normalized = url.as_str().trim_end_matches('/').to_string();
+
+    Ok(NormalizedUrl {
+        changed: normalized != trimmed,
+        url: normalized,
+        warnings,
+    })
+}
+
+#[tauri::command]
+pub fn normalize_base_url(url: String, style: UrlStyle) -> Result<NormalizedUrl, String> {
+    normalize_url(&url, style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(raw: &str, style: UrlStyle) -> String {
+        normalize_url(raw, style).unwrap().url
+    }
+
+    #[test]
+    fn anthropic_urls_drop_the_version_suffix() {
+        assert_eq!(url("https://api.example.com/v1", UrlStyle::Anthropic), "https://api.example.com");
+        assert_eq!(url("https://api.example.com/", UrlStyle::Anthropic), "https://api.example.com");
+        assert_eq!(url("https://api.example.com/v1/messages", UrlStyle::Anthropic), "https://api.example.com");
+    }
+
+    #[test]
+    fn openai_urls_get_a_version_suffix() {
+        assert_eq!(url("https://api.example.com", UrlStyle::Openai), "https://api.example.com/v1");
+        assert_eq!(url("https://api.example.com/v1/", UrlStyle::Openai), "https://api.example.com/v1");
+        assert_eq!(
+            url("https://api.example.com/api/v4/chat/completions", UrlStyle::Openai),
+            "https://api.example.com/api/v4"
+        );
+    }
+
+    #[test]
+    fn duplicated_versions_and_queries_are_removed() {
+        let normalized = normalize_url("https://api.example.com/v1/v1?key=1#top", UrlStyle::Openai).unwrap();
+        assert_eq!(normalized.url, "https://api.example.com/v1");
+        assert!(normalized.changed);
+        assert_eq!(normalized.warnings.len(), 3);
+    }
+
+    #[test]
+    fn fragments_are_reported_on_their_own() {
+        let normalized = normalize_url("https://api.example.com#top", UrlStyle::Anthropic).unwrap();
+        assert_eq!(normalized.url, "https://api.example.com");
+        assert!(normalized.changed);
+        assert_eq!(normalized.warnings, ["Removed fragment from base URL"]);
+    }
+
+    #[test]
+    fn clean_urls_are_unchanged() {
+        let normalized = normalize_url("https://api.example.com/v1", UrlStyle::Openai).unwrap();
+        assert!(!normalized.changed);
+        assert!(normalized.warnings.is_empty());
+    }
+
+    #[test]
+    fn plain_http_is_flagged_except_locally() {
+        let remote = normalize_url("http://api.example.com", UrlStyle::Anthropic).unwrap();
+        assert!(remote.warnings.iter().any(|w| w.contains("plain http")));
+        let local = normalize_url("http://localhost:8080", UrlStyle::Anthropic).unwrap();
+        assert!(local.warnings.is_empty());
+    }
+
+    #[test]
+    fn invalid_urls_are_rejected() {
+        assert!(normalize_url("", UrlStyle::Anthropic).is_err());
+        assert!(normalize_url("api.example.com", UrlStyle::Anthropic).is_err());
+        assert!(normalize_url("ftp://api.example.com", UrlStyle::Anthropic).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use serde::Serialize;
+use url::Url;
+
+use crate::profiles::Protocol;
+use crate::redact::is_masked;
+use crate::secret_refs::is_reference;
+
+/// Keys shorter than this are most likely cut off.
+const MIN_TOKEN_LEN: usize = 20;
+/// Characters that would break the quoted `export` lines in the secrets file.
+const UNSAFE_CHARS: [char; 5] = ['"', '\'', '\\', '$', '`'];
+
+/// The key format of a provider, recognised by the host of the base URL.
+/// Gateways and self-hosted endpoints match none of these and only get the
+/// generic checks.
+struct ProviderRules {
+    name: &'static str,
+    /// Matched against the end of the host, so subdomains count too.
+    hosts: &'static [&'static str],
+    prefixes: &'static [&'static str],
+}
+
+const PROVIDERS: [ProviderRules; 6] = [
+    ProviderRules { name: "Anthropic", hosts: &["anthropic.com"], prefixes: &["sk-ant-"] },
+    ProviderRules { name: "OpenAI", hosts: &["openai.com"], prefixes: &["sk-"] },
+    ProviderRules { name: "OpenRouter", hosts: &["openrouter.ai"], prefixes: &["sk-or-"] },
+    ProviderRules { name: "DeepSeek", hosts: &["deepseek.com"], prefixes: &["sk-"] },
+    ProviderRules { name: "Moonshot", hosts: &["moonshot.cn", "moonshot.ai"], prefixes: &["sk-"] },
+    ProviderRules { name: "Groq", hosts: &["groq.com"], prefixes: &["gsk_"] },
+];
+
+/// Prefixes that give away which API family a key belongs to.
+const ANTHROPIC_PREFIXES: [&str; 1] = ["sk-ant-"];
+const OPENAI_PREFIXES: [&str; 2] = ["sk-proj-", "sk-svcacct-"];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Saved anyway.
+    Warning,
+    /// Blocks the write.
+    Error,
+}
+
+#[derive(Serialize)]
+pub struct TokenIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+fn issue(severity: Severity, message: String) -> TokenIssue {
+    TokenIssue { severity, message }
+}
+
+fn provider_for(base_url: &str) -> Option<&'static ProviderRules> {
+    let url = Url::parse(base_url.trim()).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    PROVIDERS.iter().find(|p| {
+        p.hosts
+            .iter()
+            .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+    })
+}
+
+/// Checks a token before it is written into a tool config that speaks
+/// `protocol` at `base_url`. References and masked tokens are not checked
+/// here; the value they stand for is checked when it is written.
+pub(crate) fn check_token(token: &str, protocol: Protocol, base_url: &str) -> Vec<TokenIssue> {
+    let mut issues = Vec::new();
+
+    if is_reference(token) || is_masked(token) {
+        return issues;
+    }
+    if token.trim().is_empty() {
+        issues.push(issue(Severity::Error, "The token is empty".to_string()));
+        return issues;
+    }
+
+    if token.trim() != token {
+        issues.push(issue(Severity::Warning, "Leading or trailing whitespace was removed".to_string()));
+    }
+    let token = token.trim();
+
+    if token.chars().any(char::is_whitespace) {
+        issues.push(issue(Severity::Error, "The token contains a space or line break".to_string()));
+    }
+    if let Some(c) = token
+        .chars()
+        .find(|c| UNSAFE_CHARS.contains(c) || !(c.is_ascii_graphic() || c.is_whitespace()))
+    {
+        issues.push(issue(Severity::Error, format!("The token contains {:?}, which API keys do not", c)));
+    }
+    if token.chars().count() < MIN_TOKEN_LEN {
+        issues.push(issue(Severity::Warning, "The token is shorter than API keys usually are".to_string()));
+    }
+
+    match provider_for(base_url) {
+        Some(provider) if !provider.prefixes.iter().any(|p| token.starts_with(p)) => {
+            issues.push(issue(
+                Severity::Error,
+                format!("{} keys start with {}", provider.name, provider.prefixes.join(" or ")),
+            ));
+        }
+        _ => {
+            if protocol == Protocol::Anthropic && OPENAI_PREFIXES.iter().any(|p| token.starts_with(p)) {
+                issues.push(issue(Severity::Warning, "This looks like an OpenAI key".to_string()));
+            }
+            if protocol == Protocol::Openai && ANTHROPIC_PREFIXES.iter().any(|p| token.starts_with(p)) {
+                issues.push(issue(Severity::Warning, "This looks like an Anthropic key".to_string()));
+            }
+        }
+    }
+
+    issues
+}
+
+/// Runs `check_token` for a write: errors fail it, warnings are added to
+/// `warnings`, and the token comes back trimmed.
+pub(crate) fn checked_token(
+    token: &str,
+    protocol: Protocol,
+    base_url: &str,
+    warnings: &mut Vec<String>,
+) -> Result<String, String> {
+    let issues = check_token(token, protocol, base_url);
+
+    let errors: Vec<&str> = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .map(|i| i.message.as_str())
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    warnings.extend(issues.into_iter().map(|i| i.message));
+    Ok(token.trim().to_string())
+}
+
+/// Checks a token as the user types it, so the form can show problems inline.
+#[tauri::command]
+pub fn validate_token(token: String, protocol: Protocol, base_url: String) -> Vec<TokenIssue> {
+    check_token(&token, protocol, &base_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GATEWAY: &str = "https://gateway.example.com";
+
+    fn errors(token: &str, protocol: Protocol, base_url: &str) -> Vec<String> {
+        check_token(token, protocol, base_url)
+            .into_iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| i.message)
+            .collect()
+    }
+
+    #[test]
+    fn well_formed_tokens_pass() {
+        assert!(check_token("sk-ant-api03-abcdef123456", Protocol::Anthropic, "https://api.anthropic.com").is_empty());
+        assert!(check_token("gw-abcdefghij0123456789", Protocol::Anthropic, GATEWAY).is_empty());
+    }
+
+    #[test]
+    fn references_and_masked_tokens_are_not_checked() {
+        assert!(check_token("env:MY_TOKEN", Protocol::Anthropic, "https://api.anthropic.com").is_empty());
+        assert!(check_token("sk-…3456", Protocol::Anthropic, "https://api.anthropic.com").is_empty());
+    }
+
+    #[test]
+    fn broken_tokens_are_errors() {
+        assert_eq!(errors("  ", Protocol::Anthropic, GATEWAY), ["The token is empty"]);
+        assert_eq!(errors("abcdefghij 0123456789", Protocol::Anthropic, GATEWAY).len(), 1);
+        assert_eq!(errors("abcdefghij\"0123456789", Protocol::Anthropic, GATEWAY).len(), 1);
+        assert_eq!(errors("abcdefghij$0123456789", Protocol::Anthropic, GATEWAY).len(), 1);
+    }
+
+    #[test]
+    fn provider_prefixes_are_enforced() {
+        assert_eq!(
+            errors("sk-proj-abcdef0123456789", Protocol::Anthropic, "https://api.anthropic.com"),
+            ["Anthropic keys start with sk-ant-"]
+        );
+        assert_eq!(
+            errors("sk-abcdef0123456789abcd", Protocol::Openai, "https://api.groq.com/openai/v1"),
+            ["Groq keys start with gsk_"]
+        );
+        assert!(errors("sk-or-v1-abcdef0123456789", Protocol::Openai, "https://openrouter.ai/api/v1").is_empty());
+    }
+
+    #[test]
+    fn family_mismatches_on_gateways_are_warnings() {
+        let issues = check_token("sk-ant-api03-abcdef123456", Protocol::Openai, GATEWAY);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn checked_token_trims_and_collects_warnings() {
+        let mut warnings = Vec::new();
+        let token = checked_token(" short-token ", Protocol::Anthropic, GATEWAY, &mut warnings).unwrap();
+        assert_eq!(token, "short-token");
+        assert_eq!(warnings.len(), 2);
+        assert!(checked_token("", Protocol::Anthropic, GATEWAY, &mut warnings).is_err());
+    }
+}
@@ -0,0 +1,792 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::sync::Mutex;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::lock::require_app_unlocked;
+use crate::paths::{get_secrets_path, get_vault_path};
+use crate::perms::write_private;
+use crate::profiles::{read_profiles, write_profiles, ProviderProfile};
+use crate::redact::{fingerprint, redacted};
+use crate::secret_refs::{export_value, is_reference, resolve_token, split_export_value};
+
+const VERSION: u32 = 1;
+/// Binds the ciphertext to the format version, so a file cannot be
+/// downgraded to an older layout without failing authentication.
+const ASSOCIATED_DATA: &[u8] = b"my-switch-vault-v1";
+pub(crate) const VAULT_PREFIX: &str = "vault:";
+/// Lets the command line open the vault, since it runs in its own process.
+const PASSPHRASE_ENV: &str = "MY_SWITCH_VAULT_PASSPHRASE";
+const MIN_PASSPHRASE_LEN: usize = 8;
+const TOKEN_KEYS: [&str; 2] = ["ANTHROPIC_AUTH_TOKEN", "CS_AUTH_TOKEN"];
+
+#[derive(Serialize, Deserialize, Clone)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+}
+
+/// The vault as stored on disk. Only the ciphertext depends on the
+/// passphrase; the KDF parameters travel with it so they can be raised later.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct VaultData {
+    tokens: BTreeMap<String, String>,
+}
+
+struct Unlocked {
+    key: [u8; 32],
+    kdf: KdfParams,
+    data: VaultData,
+}
+
+impl Drop for Unlocked {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.data.tokens.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+static VAULT: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+impl VaultData {
+    /// Stores the entries and returns how many of them were new or changed.
+    fn store(&mut self, entries: Vec<(String, String)>) -> usize {
+        let mut changed = 0;
+        for (id, token) in entries {
+            if self.tokens.get(&id) != Some(&token) {
+                changed += 1;
+            }
+            if let Some(mut replaced) = self.tokens.insert(id, token) {
+                replaced.zeroize();
+            }
+        }
+        changed
+    }
+
+    /// Puts `new` into every entry that holds `old`, renaming a group entry
+    /// after the new token. Returns the renames, old id first.
+    fn replace(&mut self, old: &str, new: &str) -> Vec<(String, String)> {
+        let moves: Vec<(String, String)> = self
+            .tokens
+            .iter()
+            .filter(|(_, t)| *t == old)
+            .map(|(id, _)| {
+                let renamed = if *id == group_entry(old) { group_entry(new) } else { id.clone() };
+                (id.clone(), renamed)
+            })
+            .collect();
+
+        for (from, to) in &moves {
+            self.tokens.remove(from);
+            self.tokens.insert(to.clone(), new.to_string());
+        }
+        moves
+    }
+
+    /// Undoes `replace`.
+    fn restore(&mut self, moves: &[(String, String)], old: &str) {
+        for (from, to) in moves {
+            self.tokens.remove(to);
+            self.tokens.insert(from.clone(), old.to_string());
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    /// Number of stored tokens, known only while unlocked.
+    pub entries: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct MigrationReport {
+    /// Group tokens now stored in the vault. Inactive groups in the secrets
+    /// file now hold a reference instead.
+    pub groups: usize,
+    pub profiles: usize,
+}
+
+fn new_kdf() -> KdfParams {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    KdfParams {
+        m_cost: Params::DEFAULT_M_COST,
+        t_cost: Params::DEFAULT_T_COST,
+        p_cost: Params::DEFAULT_P_COST,
+        salt: STANDARD.encode(salt),
+    }
+}
+
+fn derive_key(passphrase: &str, kdf: &KdfParams) -> Result<[u8; 32], String> {
+    let salt = STANDARD
+        .decode(&kdf.salt)
+        .map_err(|e| format!("Failed to decode vault salt: {}", e))?;
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| format!("Invalid vault parameters: {}", e))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Failed to derive vault key: {}", e))?;
+    Ok(key)
+}
+
+/// A vault holding `data`, keyed by `passphrase` with a fresh salt.
+fn keyed(passphrase: &str, data: VaultData) -> Result<Unlocked, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    let kdf = new_kdf();
+    Ok(Unlocked {
+        key: derive_key(passphrase, &kdf)?,
+        kdf,
+        data,
+    })
+}
+
+fn cipher(key: &[u8; 32]) -> XChaCha20Poly1305 {
+    XChaCha20Poly1305::new(Key::from_slice(key))
+}
+
+fn encrypt(unlocked: &Unlocked) -> Result<VaultFile, String> {
+    let mut plaintext = serde_json::to_vec(&unlocked.data)
+        .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+
+    // A fresh nonce for every write; XChaCha's 192-bit nonces make random ones safe
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher(&unlocked.key)
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: ASSOCIATED_DATA })
+        .map_err(|_| "Failed to encrypt vault".to_string());
+    plaintext.zeroize();
+    let ciphertext = ciphertext?;
+
+    Ok(VaultFile {
+        version: VERSION,
+        kdf: unlocked.kdf.clone(),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt(file: &VaultFile, key: &[u8; 32]) -> Result<VaultData, String> {
+    if file.version != VERSION {
+        return Err(format!("Unsupported vault version: {}", file.version));
+    }
+
+    let nonce = STANDARD
+        .decode(&file.nonce)
+        .map_err(|e| format!("Failed to decode vault nonce: {}", e))?;
+    let ciphertext = STANDARD
+        .decode(&file.ciphertext)
+        .map_err(|e| format!("Failed to decode vault: {}", e))?;
+    if nonce.len() != 24 {
+        return Err("Vault nonce has the wrong length".to_string());
+    }
+
+    let mut plaintext = cipher(key)
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: ASSOCIATED_DATA })
+        .map_err(|_| "Wrong passphrase, or the vault is corrupted".to_string())?;
+
+    let data = serde_json::from_slice(&plaintext)
+        .map_err(|e| format!("Failed to parse vault: {}", redacted(e)));
+    plaintext.zeroize();
+    data
+}
+
+/// Data sealed under a passphrase of its own with the vault's KDF and
+/// cipher, for files that leave the machine such as profile bundles.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Sealed {
+    kdf: KdfParams,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub(crate) fn seal(plaintext: &[u8], passphrase: &str, aad: &[u8]) -> Result<Sealed, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+
+    let kdf = new_kdf();
+    let mut key = derive_key(passphrase, &kdf)?;
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher(&key)
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| "Failed to encrypt".to_string());
+    key.zeroize();
+
+    Ok(Sealed {
+        kdf,
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext?),
+    })
+}
+
+pub(crate) fn unseal(sealed: &Sealed, passphrase: &str, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = STANDARD
+        .decode(&sealed.nonce)
+        .map_err(|e| format!("Failed to decode nonce: {}", e))?;
+    let ciphertext = STANDARD
+        .decode(&sealed.ciphertext)
+        .map_err(|e| format!("Failed to decode ciphertext: {}", e))?;
+    if nonce.len() != 24 {
+        return Err("Nonce has the wrong length".to_string());
+    }
+
+    let mut key = derive_key(passphrase, &sealed.kdf)?;
+    let plaintext = cipher(&key)
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map_err(|_| "Wrong passphrase, or the file is corrupted".to_string());
+    key.zeroize();
+    plaintext
+}
+
+fn read_vault_file() -> Result<Option<VaultFile>, String> {
+    let path = get_vault_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read vault: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse vault: {}", redacted(e)))
+}
+
+fn write_vault(unlocked: &Unlocked) -> Result<(), String> {
+    let path = get_vault_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create vault directory: {}", e))?;
+    }
+
+    let pretty_json = serde_json::to_string_pretty(&encrypt(unlocked)?)
+        .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+
+    write_private(&path, pretty_json)
+        .map_err(|e| format!("Failed to write vault: {}", e))
+}
+
+fn open(file: VaultFile, passphrase: &str) -> Result<Unlocked, String> {
+    let key = derive_key(passphrase, &file.kdf)?;
+    let data = decrypt(&file, &key)?;
+    Ok(Unlocked { key, kdf: file.kdf, data })
+}
+
+fn open_with(passphrase: &str) -> Result<Unlocked, String> {
+    let file = read_vault_file()?.ok_or_else(|| "No vault has been created".to_string())?;
+    open(file, passphrase)
+}
+
+/// Runs `f` on the unlocked vault. A locked vault is opened with the
+/// passphrase from the environment when there is one.
+fn with_unlocked<T>(f: impl FnOnce(&mut Unlocked) -> Result<T, String>) -> Result<T, String> {
+    let mut vault = VAULT.lock().map_err(|_| "Vault state is poisoned".to_string())?;
+
+    if vault.is_none() {
+        if let Some(passphrase) = env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()) {
+            *vault = Some(open_with(&passphrase)?);
+        }
+    }
+
+    match vault.as_mut() {
+        Some(unlocked) => f(unlocked),
+        None => Err(format!("Vault is locked; unlock it in the app or set {}", PASSPHRASE_ENV)),
+    }
+}
+
+pub(crate) fn vault_exists() -> bool {
+    get_vault_path().map(|p| p.exists()).unwrap_or(false)
+}
+
+fn is_unlocked() -> bool {
+    VAULT.lock().map(|v| v.is_some()).unwrap_or(false)
+}
+
+/// Fails while a vault exists but is locked, for commands that hand out
+/// plaintext tokens. Without a vault there is nothing to unlock.
+pub(crate) fn require_unlocked() -> Result<(), String> {
+    if !vault_exists() {
+        return Ok(());
+    }
+    with_unlocked(|_| Ok(()))
+}
+
+pub(crate) fn reference(id: &str) -> String {
+    format!("{}{}", VAULT_PREFIX, id)
+}
+
+pub(crate) fn profile_entry(profile_id: &str) -> String {
+    format!("profile:{}", profile_id)
+}
+
+/// Groups have no stable id, so their entries are named after the token.
+pub(crate) fn group_entry(token: &str) -> String {
+    format!("group:{}", fingerprint(token))
+}
+
+/// The token stored under `id`, for resolving a `vault:` reference.
+pub(crate) fn lookup_token(id: &str) -> Result<String, String> {
+    with_unlocked(|vault| {
+        vault
+            .data
+            .tokens
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Vault has no entry {}", id))
+    })
+}
+
+/// Stores tokens under the given ids and returns a reference for each.
+pub(crate) fn store_tokens(entries: Vec<(String, String)>) -> Result<Vec<String>, String> {
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    with_unlocked(|vault| {
+        let references = entries.iter().map(|(id, _)| reference(id)).collect();
+        vault.data.store(entries);
+        write_vault(vault)?;
+        Ok(references)
+    })
+}
+
+/// Ids of the entries that hold `token`.
+pub(crate) fn entries_holding(token: &str) -> Result<Vec<String>, String> {
+    if !vault_exists() {
+        return Ok(Vec::new());
+    }
+    with_unlocked(|vault| {
+        Ok(vault
+            .data
+            .tokens
+            .iter()
+            .filter(|(_, t)| *t == token)
+            .map(|(id, _)| id.clone())
+            .collect())
+    })
+}
+
+/// Puts `new` into every entry that holds `old`. A group entry is renamed
+/// after the new token. Returns the renames, old id first, so they can be undone.
+pub(crate) fn replace_token(old: &str, new: &str) -> Result<Vec<(String, String)>, String> {
+    if entries_holding(old)?.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    with_unlocked(|vault| {
+        let moves = vault.data.replace(old, new);
+        write_vault(vault)?;
+        Ok(moves)
+    })
+}
+
+/// Reverts `replace_token`.
+pub(crate) fn restore_token(moves: &[(String, String)], old: &str) -> Result<(), String> {
+    if moves.is_empty() {
+        return Ok(());
+    }
+    with_unlocked(|vault| {
+        vault.data.restore(moves, old);
+        write_vault(vault)
+    })
+}
+
+/// Drops an entry if the vault is open. Best effort: a leftover entry is
+/// still encrypted and is overwritten if the id is reused.
+pub(crate) fn forget_token(id: &str) {
+    if !is_unlocked() {
+        return;
+    }
+    let _ = with_unlocked(|vault| {
+        if vault.data.tokens.remove(id).is_some() {
+            write_vault(vault)?;
+        }
+        Ok(())
+    });
+}
+
+/// Keeps plaintext in the secrets file only where the shell needs it.
+/// Active `export` lines that hold a reference get the token, with the
+/// reference kept after it, and go back to the bare reference once inactive.
+/// While the vault is `unlocked`, tokens of inactive groups are moved into
+/// it. With `store_active`, active tokens are stored as well, but stay in
+/// place. Returns the new content and the entries to store.
+fn seal_lines(
+    content: &str,
+    token_key: &str,
+    store_active: bool,
+    unlocked: bool,
+) -> Result<(String, Vec<(String, String)>), String> {
+    let active_prefix = format!("export {}=", token_key);
+    let inactive_prefix = format!("#export {}=", token_key);
+
+    let mut lines = Vec::new();
+    let mut entries = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if let Some(raw) = trimmed.strip_prefix(&active_prefix) {
+            let (value, kept) = split_export_value(raw);
+            if kept.is_none() && is_reference(value) {
+                lines.push(format!("{}{}", active_prefix, export_value(&resolve_token(value)?, Some(value))));
+                continue;
+            }
+            if store_active && kept.is_none() && !value.is_empty() {
+                entries.push((group_entry(value), value.to_string()));
+            }
+        } else if let Some(raw) = trimmed.strip_prefix(&inactive_prefix) {
+            let (value, kept) = split_export_value(raw);
+            if let Some(reference) = kept {
+                lines.push(format!("{}{}", inactive_prefix, export_value(reference, None)));
+                continue;
+            }
+            if unlocked && !value.is_empty() && !is_reference(value) {
+                let id = group_entry(value);
+                lines.push(format!("{}\"{}\"", inactive_prefix, reference(&id)));
+                entries.push((id, value.to_string()));
+                continue;
+            }
+        }
+
+        lines.push(line.to_string());
+    }
+
+    Ok((lines.join("\n"), entries))
+}
+
+/// Run on the secrets file after a switch moves groups between active and inactive.
+pub(crate) fn seal_groups(content: &str, token_key: &str) -> Result<String, String> {
+    let (content, entries) = seal_lines(content, token_key, false, is_unlocked())?;
+    store_tokens(entries)?;
+    Ok(content)
+}
+
+/// The secrets file with every group token moved into the vault, and the
+/// entries to store.
+fn migrate_secrets(content: &str) -> Result<(String, Vec<(String, String)>), String> {
+    let mut updated = content.to_string();
+    let mut entries = Vec::new();
+    for token_key in TOKEN_KEYS {
+        let (sealed, stored) = seal_lines(&updated, token_key, true, true)?;
+        updated = sealed;
+        entries.extend(stored);
+    }
+    Ok((updated, entries))
+}
+
+/// Stores the plaintext tokens of `profiles` in `data` and points the
+/// profiles at their entries. Returns how many profiles were moved.
+fn migrate_profiles(data: &mut VaultData, profiles: &mut [ProviderProfile]) -> usize {
+    let mut migrated = 0;
+    for profile in profiles
+        .iter_mut()
+        .filter(|p| !p.api_key.is_empty() && !is_reference(p.api_key.expose()))
+    {
+        let id = profile_entry(&profile.id);
+        data.store(vec![(id.clone(), profile.api_key.expose().to_string())]);
+        profile.api_key = reference(&id).into();
+        migrated += 1;
+    }
+    migrated
+}
+
+#[tauri::command]
+pub fn vault_status() -> Result<VaultStatus, String> {
+    let vault = VAULT.lock().map_err(|_| "Vault state is poisoned".to_string())?;
+    Ok(VaultStatus {
+        exists: vault_exists(),
+        unlocked: vault.is_some(),
+        entries: vault.as_ref().map(|v| v.data.tokens.len()),
+    })
+}
+
+/// Opens the vault, creating an empty one with this passphrase if there is none yet.
+#[tauri::command]
+pub fn unlock_vault(passphrase: String) -> Result<VaultStatus, String> {
+    let unlocked = if vault_exists() {
+        open_with(&passphrase)?
+    } else {
+        let unlocked = keyed(&passphrase, VaultData::default())?;
+        write_vault(&unlocked)?;
+        unlocked
+    };
+
+    *VAULT.lock().map_err(|_| "Vault state is poisoned".to_string())? = Some(unlocked);
+    vault_status()
+}
+
+#[tauri::command]
+pub fn lock_vault() -> Result<VaultStatus, String> {
+    *VAULT.lock().map_err(|_| "Vault state is poisoned".to_string())? = None;
+    vault_status()
+}
+
+/// Re-encrypts the vault under a new passphrase, with a fresh salt.
+#[tauri::command]
+pub fn change_vault_passphrase(current: String, new: String) -> Result<VaultStatus, String> {
+    require_app_unlocked()?;
+    if new.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+
+    let mut current = open_with(&current)?;
+    let unlocked = keyed(&new, std::mem::take(&mut current.data))?;
+    write_vault(&unlocked)?;
+
+    *VAULT.lock().map_err(|_| "Vault state is poisoned".to_string())? = Some(unlocked);
+    vault_status()
+}
+
+/// Moves plaintext tokens into the vault: every group in the secrets file
+/// and every saved profile. Active groups keep their plaintext, since new
+/// shells export it.
+#[tauri::command]
+pub fn migrate_to_vault() -> Result<MigrationReport, String> {
+    require_app_unlocked()?;
+    if !is_unlocked() {
+        return Err("Unlock the vault before migrating".to_string());
+    }
+
+    let secrets_path = get_secrets_path()?;
+    let content = if secrets_path.exists() {
+        fs::read_to_string(&secrets_path).map_err(|e| format!("Failed to read file: {}", e))?
+    } else {
+        String::new()
+    };
+    // Active references are resolved here, before the vault is held below
+    let (updated, entries) = migrate_secrets(&content)?;
+    let mut profiles = read_profiles()?;
+
+    // The vault is written first, so nothing ever points at a missing entry
+    let (groups, migrated) = with_unlocked(|vault| {
+        let groups = vault.data.store(entries);
+        let migrated = migrate_profiles(&mut vault.data, &mut profiles);
+        write_vault(vault)?;
+        Ok((groups, migrated))
+    })?;
+
+    if updated != content {
+        write_private(&secrets_path, updated)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+    if migrated > 0 {
+        write_profiles(&profiles)?;
+    }
+
+    Ok(MigrationReport { groups, profiles: migrated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::Protocol;
+    use crate::secret::Secret;
+
+    const PASSPHRASE: &str = "correct horse battery";
+    const TOKEN: &str = "sk-vault-token-123456";
+
+    fn data(entries: &[(&str, &str)]) -> VaultData {
+        VaultData {
+            tokens: entries.iter().map(|(id, t)| (id.to_string(), t.to_string())).collect(),
+        }
+    }
+
+    /// Writes the vault as `write_vault` does and reads it back from disk.
+    fn stored(unlocked: &Unlocked) -> VaultFile {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.json");
+        write_private(&path, serde_json::to_string_pretty(&encrypt(unlocked).unwrap()).unwrap()).unwrap();
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    fn tampered(encoded: &str) -> String {
+        let mut bytes = STANDARD.decode(encoded).unwrap();
+        bytes[0] ^= 1;
+        STANDARD.encode(bytes)
+    }
+
+    #[test]
+    fn sealed_data_round_trips() {
+        let sealed = seal(b"profiles", PASSPHRASE, b"aad").unwrap();
+        assert_eq!(unseal(&sealed, PASSPHRASE, b"aad").unwrap(), b"profiles");
+        assert!(seal(b"profiles", "short", b"aad").is_err());
+    }
+
+    #[test]
+    fn sealed_data_needs_the_passphrase_and_associated_data() {
+        let sealed = seal(b"profiles", PASSPHRASE, b"aad").unwrap();
+        assert!(unseal(&sealed, "wrong horse battery", b"aad").is_err());
+        assert!(unseal(&sealed, PASSPHRASE, b"other").is_err());
+    }
+
+    #[test]
+    fn tampered_sealed_data_fails() {
+        let mut sealed = seal(b"profiles", PASSPHRASE, b"aad").unwrap();
+        sealed.ciphertext = tampered(&sealed.ciphertext);
+        assert!(unseal(&sealed, PASSPHRASE, b"aad").is_err());
+    }
+
+    #[test]
+    fn vault_files_round_trip() {
+        let unlocked = keyed(PASSPHRASE, data(&[("profile:work", TOKEN)])).unwrap();
+        let file = stored(&unlocked);
+        assert!(!file.ciphertext.contains(TOKEN));
+
+        let opened = open(file, PASSPHRASE).unwrap();
+        assert_eq!(opened.data.tokens.get("profile:work").map(String::as_str), Some(TOKEN));
+    }
+
+    #[test]
+    fn vault_files_refuse_a_wrong_passphrase() {
+        let unlocked = keyed(PASSPHRASE, data(&[("profile:work", TOKEN)])).unwrap();
+        assert!(open(stored(&unlocked), "wrong horse battery").is_err());
+        assert!(keyed("short", VaultData::default()).is_err());
+    }
+
+    #[test]
+    fn tampered_vault_files_fail_authentication() {
+        let unlocked = keyed(PASSPHRASE, data(&[("profile:work", TOKEN)])).unwrap();
+        let mut file = stored(&unlocked);
+        file.ciphertext = tampered(&file.ciphertext);
+        assert!(open(file, PASSPHRASE).is_err());
+
+        let mut file = stored(&unlocked);
+        file.version = VERSION + 1;
+        assert!(open(file, PASSPHRASE).is_err());
+    }
+
+    #[test]
+    fn changing_the_passphrase_re_encrypts() {
+        let original = keyed(PASSPHRASE, data(&[("profile:work", TOKEN)])).unwrap();
+        let mut current = open(stored(&original), PASSPHRASE).unwrap();
+
+        let rekeyed = keyed("new horse battery", std::mem::take(&mut current.data)).unwrap();
+        let file = stored(&rekeyed);
+        assert_ne!(file.kdf.salt, original.kdf.salt);
+        assert!(open(stored(&rekeyed), PASSPHRASE).is_err());
+
+        let opened = open(file, "new horse battery").unwrap();
+        assert_eq!(opened.data.tokens.get("profile:work").map(String::as_str), Some(TOKEN));
+    }
+
+    #[test]
+    fn seal_lines_resolves_active_references_and_keeps_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("key");
+        fs::write(&key, "sk-from-file-123456\n").unwrap();
+        let reference = format!("file:{}", key.display());
+        let content = format!(
+            "export CS_BASE_URL=\"https://a.example.com\"\nexport CS_AUTH_TOKEN=\"{}\"\n\
+             #export CS_AUTH_TOKEN=\"sk-stale-123456\" # my-switch:ref cmd:pass show old",
+            reference
+        );
+
+        let (sealed, entries) = seal_lines(&content, "CS_AUTH_TOKEN", false, false).unwrap();
+        assert_eq!(
+            sealed,
+            format!(
+                "export CS_BASE_URL=\"https://a.example.com\"\n\
+                 export CS_AUTH_TOKEN=\"sk-from-file-123456\" # my-switch:ref {}\n\
+                 #export CS_AUTH_TOKEN=\"cmd:pass show old\"",
+                reference
+            )
+        );
+        assert!(entries.is_empty());
+
+        // Sealing again leaves a resolved line as it is
+        assert_eq!(seal_lines(&sealed, "CS_AUTH_TOKEN", false, false).unwrap().0, sealed);
+    }
+
+    #[test]
+    fn seal_lines_moves_inactive_tokens_only_while_unlocked() {
+        let content = format!("export CS_AUTH_TOKEN=\"sk-active-123456\"\n#export CS_AUTH_TOKEN=\"{}\"", TOKEN);
+
+        let (locked, entries) = seal_lines(&content, "CS_AUTH_TOKEN", false, false).unwrap();
+        assert_eq!(locked, content);
+        assert!(entries.is_empty());
+
+        let (unlocked, entries) = seal_lines(&content, "CS_AUTH_TOKEN", false, true).unwrap();
+        assert_eq!(
+            unlocked,
+            format!(
+                "export CS_AUTH_TOKEN=\"sk-active-123456\"\n#export CS_AUTH_TOKEN=\"{}\"",
+                reference(&group_entry(TOKEN))
+            )
+        );
+        assert_eq!(entries, [(group_entry(TOKEN), TOKEN.to_string())]);
+    }
+
+    #[test]
+    fn replacing_a_token_can_be_undone() {
+        let mut vault = data(&[
+            ("profile:work", TOKEN),
+            (&group_entry(TOKEN), TOKEN),
+            ("profile:other", "sk-other-token-123456"),
+        ]);
+        let before = vault.tokens.clone();
+
+        let moves = vault.replace(TOKEN, "sk-new-token-654321");
+        assert_eq!(moves.len(), 2);
+        assert_eq!(vault.tokens.get("profile:work").map(String::as_str), Some("sk-new-token-654321"));
+        assert!(vault.tokens.contains_key(&group_entry("sk-new-token-654321")));
+        assert!(!vault.tokens.contains_key(&group_entry(TOKEN)));
+        assert_eq!(vault.tokens.get("profile:other").map(String::as_str), Some("sk-other-token-123456"));
+
+        vault.restore(&moves, TOKEN);
+        assert_eq!(vault.tokens, before);
+    }
+
+    #[test]
+    fn migrating_twice_changes_nothing_more() {
+        let content = format!(
+            "export ANTHROPIC_AUTH_TOKEN=\"sk-active-123456\"\n#export ANTHROPIC_AUTH_TOKEN=\"{}\"\n",
+            TOKEN
+        );
+        let mut profiles = vec![ProviderProfile {
+            id: "work".to_string(),
+            name: "work".to_string(),
+            base_url: "https://gateway.example.com".to_string(),
+            api_key: Secret::new("sk-profile-token-123456"),
+            protocol: Protocol::Anthropic,
+            model: None,
+            key_fingerprint: None,
+        }];
+        let mut vault = VaultData::default();
+
+        let (first, entries) = migrate_secrets(&content).unwrap();
+        assert_eq!(vault.store(entries), 2);
+        assert_eq!(migrate_profiles(&mut vault, &mut profiles), 1);
+        assert_eq!(profiles[0].api_key.expose(), reference(&profile_entry("work")));
+
+        let (second, entries) = migrate_secrets(&first).unwrap();
+        assert_eq!(second, first);
+        assert_eq!(vault.store(entries), 0);
+        assert_eq!(migrate_profiles(&mut vault, &mut profiles), 0);
+        assert_eq!(vault.tokens.len(), 3);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::enforce::reapply;
+use crate::paths::get_app_settings_path;
+use crate::tools::Tool;
+
+/// Editors and the tools themselves often write a file in several steps
+/// (truncate, write, rename), so events are collected until this much
+/// time passes without a new one.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+const CHANGED_EVENT: &str = "config-changed";
+
+/// What the app itself last wrote to each file, by content hash, so the
+/// watcher can tell a switch or save made in the app from a tool rewriting
+/// its config.
+static SELF_WRITES: Mutex<Vec<(PathBuf, u64)>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Clone)]
+pub struct ConfigChanged {
+    pub tool: Tool,
+    pub path: String,
+}
+
+fn content_hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Records a write made by the app, so enforcement does not revert it.
+pub(crate) fn note_self_write(path: &Path, contents: &[u8]) {
+    if let Ok(mut writes) = SELF_WRITES.lock() {
+        writes.retain(|(p, _)| p != path);
+        writes.push((path.to_path_buf(), content_hash(contents)));
+    }
+}
+
+/// Whether the file still holds what the app last wrote to it. Compared by
+/// content rather than time, since a tool may rewrite the file right after.
+fn written_by_app(path: &Path) -> bool {
+    let Ok(contents) = fs::read(path) else {
+        return false;
+    };
+    let hash = content_hash(&contents);
+    SELF_WRITES
+        .lock()
+        .map(|writes| writes.iter().any(|(p, h)| p == path && *h == hash))
+        .unwrap_or(false)
+}
+
+fn managed_files() -> Vec<(Tool, PathBuf)> {
+    Tool::ALL
+        .into_iter()
+        .flat_map(|tool| {
+            tool.config_files()
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |path| (tool, path))
+        })
+        .collect()
+}
+
+/// Directories to watch. Files are watched through their parent, since many
+/// writers replace a file by renaming over it, which ends a watch on the file
+/// itself. The app's own config dir is included so path overrides are picked up.
+fn watched_dirs(files: &[(Tool, PathBuf)]) -> BTreeSet<PathBuf> {
+    files
+        .iter()
+        .map(|(_, path)| path.as_path())
+        .chain(get_app_settings_path().ok().as_deref())
+        .filter_map(Path::parent)
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Watches the managed files and emits `config-changed` once per changed
+/// file after a burst of writes settles. Directories that do not exist yet
+/// are picked up at the next change in a watched one.
+pub(crate) fn spawn_config_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+        let Ok(mut watcher) = recommended_watcher(tx) else {
+            return;
+        };
+
+        let mut files = managed_files();
+        let mut dirs = BTreeSet::new();
+        rewatch(&mut watcher, &mut dirs, watched_dirs(&files));
+
+        loop {
+            let Ok(first) = rx.recv() else {
+                return;
+            };
+
+            let mut changed = BTreeSet::new();
+            collect(first, &mut changed);
+            let disconnected = loop {
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(event) => collect(event, &mut changed),
+                    Err(RecvTimeoutError::Timeout) => break false,
+                    Err(RecvTimeoutError::Disconnected) => break true,
+                }
+            };
+
+            let mut tools = Vec::new();
+            for path in &changed {
+                for (tool, file) in files.iter().filter(|(_, file)| file == path) {
+                    let _ = app.emit(
+                        CHANGED_EVENT,
+                        ConfigChanged { tool: *tool, path: file.display().to_string() },
+                    );
+                    if !written_by_app(path) && !tools.contains(tool) {
+                        tools.push(*tool);
+                    }
+                }
+            }
+            for tool in tools {
+                reapply(&app, tool);
+            }
+
+            if disconnected {
+                return;
+            }
+
+            // Overrides or newly created tool dirs may change what to watch
+            files = managed_files();
+            rewatch(&mut watcher, &mut dirs, watched_dirs(&files));
+        }
+    });
+}
+
+fn collect(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    let Ok(event) = event else {
+        return;
+    };
+    // Reads show up as access events; reacting to them would make every
+    // refresh trigger another one
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    changed.extend(event.paths);
+}
+
+fn rewatch(watcher: &mut RecommendedWatcher, current: &mut BTreeSet<PathBuf>, wanted: BTreeSet<PathBuf>) {
+    for dir in current.difference(&wanted) {
+        let _ = watcher.unwatch(dir);
+    }
+
+    // Directories that fail to register are left out, so they are retried next time
+    let watching = wanted
+        .into_iter()
+        .filter(|dir| current.contains(dir) || watcher.watch(dir, RecursiveMode::NonRecursive).is_ok())
+        .collect();
+    *current = watching;
+}
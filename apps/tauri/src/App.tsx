@@ -1,5 +1,6 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Eye, EyeOff } from "@my-monorepo/ui/icons";
 import { Field, FieldLabel } from "@my-monorepo/ui/components/field";
 import { Input } from "@my-monorepo/ui/components/input";
@@ -32,6 +33,72 @@ interface CodexConfig {
 	api_key: string;
 }
 
+interface ConfigChanged {
+	tool: string;
+	path: string;
+}
+
+interface FilePermissions {
+	path: string;
+	mode: string | null;
+	owner_uid: number | null;
+	problems: string[];
+}
+
+interface AppLockStatus {
+	locked: boolean;
+	available: boolean;
+	idle_timeout_secs: number | null;
+	switch_while_locked: boolean;
+}
+
+// How often user input is reported to the backend's idle timer
+const ACTIVITY_INTERVAL_MS = 30_000;
+
+interface TokenIssue {
+	severity: "warning" | "error";
+	message: string;
+}
+
+interface EditableValues {
+	baseUrl: string;
+	authToken: string;
+	anthropicBaseUrl: string;
+	anthropicAuthToken: string;
+	codexBaseUrl: string;
+	codexApiKey: string;
+}
+
+// Checks a token as it is typed; the backend runs the same checks on save
+function useTokenIssues(token: string, protocol: string, baseUrl: string) {
+	const [issues, setIssues] = useState<TokenIssue[]>([]);
+	useEffect(() => {
+		if (!token) {
+			setIssues([]);
+			return;
+		}
+		invoke<TokenIssue[]>("validate_token", { token, protocol, baseUrl })
+			.then(setIssues)
+			.catch(() => setIssues([]));
+	}, [token, protocol, baseUrl]);
+	return issues;
+}
+
+function TokenIssues({ issues }: { issues: TokenIssue[] }) {
+	return (
+		<>
+			{issues.map((issue) => (
+				<p
+					key={issue.message}
+					className={`text-xs ${issue.severity === "error" ? "text-red-500" : "text-amber-600"}`}
+				>
+					{issue.message}
+				</p>
+			))}
+		</>
+	);
+}
+
 function App() {
 	const [baseUrl, setBaseUrl] = useState("");
 	const [authToken, setAuthToken] = useState("");
@@ -53,6 +120,32 @@ function App() {
 	const [opencodeConfig, setOpencodeConfig] = useState<CodexConfig | null>(
 		null,
 	);
+	const [loaded, setLoaded] = useState<EditableValues | null>(null);
+	const [unsafeFiles, setUnsafeFiles] = useState<FilePermissions[]>([]);
+	const [lockStatus, setLockStatus] = useState<AppLockStatus | null>(null);
+	const [passphrase, setPassphrase] = useState("");
+	const anthropicTokenIssues = useTokenIssues(
+		anthropicAuthToken,
+		"anthropic",
+		anthropicBaseUrl,
+	);
+	const csTokenIssues = useTokenIssues(authToken, "anthropic", baseUrl);
+	const codexKeyIssues = useTokenIssues(codexApiKey, "openai", codexBaseUrl);
+	const dirtyRef = useRef(false);
+
+	const current: EditableValues = {
+		baseUrl,
+		authToken,
+		anthropicBaseUrl,
+		anthropicAuthToken,
+		codexBaseUrl,
+		codexApiKey,
+	};
+	dirtyRef.current =
+		loaded !== null &&
+		(Object.keys(current) as (keyof EditableValues)[]).some(
+			(key) => current[key] !== loaded[key],
+		);
 
 	function showMessage(msg: string, autoClear = true) {
 		setMessage(msg);
@@ -63,6 +156,125 @@ function App() {
 
 	useEffect(() => {
 		loadConfig();
+		invoke<FilePermissions[]>("audit_file_permissions")
+			.then((files) => setUnsafeFiles(files.filter((f) => f.problems.length)))
+			.catch(() => {});
+	}, []);
+
+	// Locking hides revealed tokens; the backend refuses to reveal them again
+	useEffect(() => {
+		invoke<AppLockStatus>("app_lock_status")
+			.then(setLockStatus)
+			.catch(() => {});
+		const unlisten = listen<boolean>("app-locked", (event) => {
+			invoke<AppLockStatus>("app_lock_status")
+				.then(setLockStatus)
+				.catch(() => {});
+			if (event.payload) {
+				setShowToken(false);
+				setShowAnthropicToken(false);
+				setShowCodexKey(false);
+				loadConfig();
+			}
+		});
+
+		let last = 0;
+		const onActivity = () => {
+			if (Date.now() - last > ACTIVITY_INTERVAL_MS) {
+				last = Date.now();
+				invoke("record_activity").catch(() => {});
+			}
+		};
+		window.addEventListener("keydown", onActivity);
+		window.addEventListener("pointerdown", onActivity);
+		window.addEventListener("pointermove", onActivity);
+		return () => {
+			unlisten.then((fn) => fn());
+			window.removeEventListener("keydown", onActivity);
+			window.removeEventListener("pointerdown", onActivity);
+			window.removeEventListener("pointermove", onActivity);
+		};
+	}, []);
+
+	async function lockApp() {
+		try {
+			setLockStatus(await invoke<AppLockStatus>("lock_app"));
+		} catch (error) {
+			showMessage(`Failed to lock: ${error}`, false);
+		}
+	}
+
+	async function unlockApp() {
+		try {
+			setLockStatus(await invoke<AppLockStatus>("unlock_app", { passphrase }));
+			setPassphrase("");
+			showMessage("Unlocked!");
+		} catch (error) {
+			showMessage(`Failed to unlock: ${error}`, false);
+		}
+	}
+
+	async function fixPermissions() {
+		try {
+			const files = await invoke<FilePermissions[]>("fix_file_permissions", {
+				paths: unsafeFiles.map((f) => f.path),
+			});
+			const remaining = files.filter((f) => f.problems.length);
+			setUnsafeFiles(remaining);
+			showMessage(
+				remaining.length
+					? `Failed to fix ${remaining.map((f) => f.path).join(", ")}`
+					: "Permissions fixed!",
+				!remaining.length,
+			);
+		} catch (error) {
+			showMessage(`Failed to fix permissions: ${error}`, false);
+		}
+	}
+
+	// Tokens arrive masked; the full value is fetched only when asked to show it
+	async function toggleReveal(
+		selector: string,
+		value: string,
+		setValue: (value: string) => void,
+		shown: boolean,
+		setShown: (shown: boolean) => void,
+	) {
+		if (!shown && value.includes("…")) {
+			try {
+				setValue(await invoke<string>("reveal_token", { profileId: selector }));
+			} catch (error) {
+				showMessage(`Failed to reveal token: ${error}`, false);
+				return;
+			}
+		}
+		setShown(!shown);
+	}
+
+	function groupSelector(
+		family: string,
+		groups: { auth_token: string }[],
+		token: string,
+	) {
+		const index = groups.findIndex((g) => g.auth_token === token);
+		return index === -1 ? family : `${family}:${index}`;
+	}
+
+	// Files edited outside the app: refresh, unless that would discard edits
+	useEffect(() => {
+		const unlisten = listen<ConfigChanged>("config-changed", (event) => {
+			if (dirtyRef.current) {
+				showMessage(
+					`${event.payload.path} changed on disk. Your unsaved edits are stale.`,
+					false,
+				);
+			} else {
+				loadConfig();
+			}
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
 	}, []);
 
 	async function loadConfig() {
@@ -94,6 +306,14 @@ function App() {
 			setAnthropicAuthToken(anthropicConfig.auth_token);
 			setDroidConfig(droid);
 			setOpencodeConfig(opencode);
+			setLoaded({
+				baseUrl: envConfig.cs_base_url,
+				authToken: envConfig.cs_auth_token,
+				anthropicBaseUrl: anthropicConfig.base_url,
+				anthropicAuthToken: anthropicConfig.auth_token,
+				codexBaseUrl: codexConfig.base_url,
+				codexApiKey: codexConfig.api_key,
+			});
 		} catch (error) {
 			setMessage(`Failed to load config: ${error}`);
 		} finally {
@@ -141,6 +361,7 @@ function App() {
 					},
 				}),
 			]);
+			setLoaded(current);
 			showMessage("Saved!");
 		} catch (error) {
 			showMessage(`Failed to save: ${error}`, false);
@@ -200,6 +421,27 @@ function App() {
 				Environment Configuration
 			</h1>
 
+			{lockStatus?.locked && (
+				<div className="flex items-center justify-center gap-3 text-sm">
+					<span>
+						{lockStatus.switch_while_locked
+							? "Locked. Switching groups still works."
+							: "Locked."}
+					</span>
+					<Input
+						type="password"
+						placeholder="Vault passphrase"
+						value={passphrase}
+						onChange={(e) => setPassphrase(e.target.value)}
+						onKeyDown={(e) => e.key === "Enter" && unlockApp()}
+						className="h-8 w-56"
+					/>
+					<Button size="sm" onClick={unlockApp} disabled={!passphrase}>
+						Unlock
+					</Button>
+				</div>
+			)}
+
 			<div className="grid grid-cols-3 gap-6">
 				{/* Claude Code */}
 				<section className="space-y-3">
@@ -249,7 +491,19 @@ function App() {
 							/>
 							<button
 								type="button"
-								onClick={() => setShowAnthropicToken(!showAnthropicToken)}
+								onClick={() =>
+									toggleReveal(
+										groupSelector(
+											"anthropic",
+											anthropicConfigGroups,
+											anthropicAuthToken,
+										),
+										anthropicAuthToken,
+										setAnthropicAuthToken,
+										showAnthropicToken,
+										setShowAnthropicToken,
+									)
+								}
 								className="absolute right-2.5 top-1/2 -translate-y-1/2 text-muted-foreground hover:text-foreground"
 							>
 								{showAnthropicToken ? (
@@ -259,6 +513,7 @@ function App() {
 								)}
 							</button>
 						</div>
+						<TokenIssues issues={anthropicTokenIssues} />
 					</Field>
 				</section>
 
@@ -310,7 +565,15 @@ function App() {
 							/>
 							<button
 								type="button"
-								onClick={() => setShowToken(!showToken)}
+								onClick={() =>
+									toggleReveal(
+										groupSelector("cs", csConfigGroups, authToken),
+										authToken,
+										setAuthToken,
+										showToken,
+										setShowToken,
+									)
+								}
 								className="absolute right-2.5 top-1/2 -translate-y-1/2 text-muted-foreground hover:text-foreground"
 							>
 								{showToken ? (
@@ -320,6 +583,7 @@ function App() {
 								)}
 							</button>
 						</div>
+						<TokenIssues issues={csTokenIssues} />
 					</Field>
 				</section>
 
@@ -350,7 +614,15 @@ function App() {
 							/>
 							<button
 								type="button"
-								onClick={() => setShowCodexKey(!showCodexKey)}
+								onClick={() =>
+									toggleReveal(
+										"tool:codex",
+										codexApiKey,
+										setCodexApiKey,
+										showCodexKey,
+										setShowCodexKey,
+									)
+								}
 								className="absolute right-2.5 top-1/2 -translate-y-1/2 text-muted-foreground hover:text-foreground"
 							>
 								{showCodexKey ? (
@@ -360,6 +632,7 @@ function App() {
 								)}
 							</button>
 						</div>
+						<TokenIssues issues={codexKeyIssues} />
 					</Field>
 				</section>
 			</div>
@@ -383,7 +656,24 @@ function App() {
 					>
 						Codex → OpenCode
 					</Button>
+					{lockStatus?.available && !lockStatus.locked && (
+						<Button variant="outline" onClick={lockApp}>
+							Lock
+						</Button>
+					)}
 				</div>
+				{unsafeFiles.length > 0 && (
+					<div className="flex items-center gap-3 text-sm text-amber-600">
+						<span>
+							{unsafeFiles.length === 1
+								? `${unsafeFiles[0].path} is ${unsafeFiles[0].problems.join(", ")}`
+								: `${unsafeFiles.length} credential files are accessible to others`}
+						</span>
+						<Button size="sm" variant="outline" onClick={fixPermissions}>
+							Fix permissions
+						</Button>
+					</div>
+				)}
 				<p
 					className={`text-sm h-5 ${message.includes("Failed") ? "text-red-500" : "text-green-500"}`}
 				>